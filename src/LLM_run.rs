@@ -1,13 +1,84 @@
 // LLM_runner.rs - Handles all LLM API interactions
 
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::env;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use futures::future;
+use reqwest::header::RETRY_AFTER;
 use reqwest::Client;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio::time::{sleep, Instant};
+use tracing::{debug, instrument, warn};
+use crate::cache::DiskCache;
+use crate::error::ScraperError;
+use crate::parse::parse_labeled_sections;
+use crate::template::PromptTemplate;
+use crate::text::{chunk_text, detect_language, estimate_tokens, split_sentences, truncate_chars, truncate_to_tokens};
 
 // Gemini API request structures
 #[derive(Serialize)]
 pub struct GeminiRequest {
     pub contents: Vec<Content>,
+    #[serde(rename = "generationConfig", skip_serializing_if = "Option::is_none")]
+    pub generation_config: Option<GenerationConfig>,
+    #[serde(rename = "safetySettings", skip_serializing_if = "Vec::is_empty")]
+    pub safety_settings: Vec<SafetySetting>,
+    #[serde(rename = "systemInstruction", skip_serializing_if = "Option::is_none")]
+    pub system_instruction: Option<Content>,
+}
+
+// Blocking threshold for one harm category, e.g. HARM_CATEGORY_HARASSMENT / BLOCK_ONLY_HIGH
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SafetySetting {
+    pub category: String,
+    pub threshold: String,
+}
+
+impl SafetySetting {
+    pub fn new(category: &str, threshold: &str) -> Self {
+        Self {
+            category: category.to_string(),
+            threshold: threshold.to_string(),
+        }
+    }
+}
+
+// Sampling and output-length controls; unset fields are left to the model defaults
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<serde_json::Value>,
+}
+
+impl GenerationConfig {
+    // Fill any unset fields from `defaults`
+    pub fn or(self, defaults: &GenerationConfig) -> GenerationConfig {
+        GenerationConfig {
+            temperature: self.temperature.or(defaults.temperature),
+            top_p: self.top_p.or(defaults.top_p),
+            top_k: self.top_k.or(defaults.top_k),
+            max_output_tokens: self.max_output_tokens.or(defaults.max_output_tokens),
+            response_mime_type: self.response_mime_type.or_else(|| defaults.response_mime_type.clone()),
+            response_schema: self.response_schema.or_else(|| defaults.response_schema.clone()),
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -23,16 +94,39 @@ pub struct Part {
 // Gemini API response structures
 #[derive(Deserialize, Debug)]
 pub struct GeminiResponse {
+    #[serde(default)]
     pub candidates: Vec<Candidate>,
+    #[serde(rename = "promptFeedback")]
+    pub prompt_feedback: Option<PromptFeedback>,
+    #[serde(rename = "usageMetadata")]
+    pub usage_metadata: Option<UsageMetadata>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageMetadata {
+    #[serde(default)]
+    pub prompt_token_count: u64,
+    #[serde(default)]
+    pub candidates_token_count: u64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PromptFeedback {
+    #[serde(rename = "blockReason")]
+    pub block_reason: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct Candidate {
-    pub content: ResponseContent,
+    pub content: Option<ResponseContent>,
+    #[serde(rename = "finishReason")]
+    pub finish_reason: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct ResponseContent {
+    #[serde(default)]
     pub parts: Vec<ResponsePart>,
 }
 
@@ -42,79 +136,1107 @@ pub struct ResponsePart {
 }
 
 // Analysis result structures
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ContentAnalysis {
     pub summary: String,
     pub sentiment: String,
     pub key_topics: String,
     pub category: String,
+    // ISO 639-1 code detected locally, or "unknown"
+    pub language: String,
+    // Sections declared by a custom prompt template beyond the four above
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra_sections: BTreeMap<String, String>,
+    // Unparsed model response, kept when the runner is built with include_raw(true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+// Summary text with the number of sentences it actually has
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub text: String,
+    pub sentence_count: usize,
+}
+
+impl Summary {
+    pub fn new(text: String) -> Self {
+        let sentence_count = split_sentences(&text).len();
+        Self { text, sentence_count }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct SentimentResult {
     pub label: String,
-    pub confidence: f64,
+    // Percentage reported by the model; None when the label came from the keyword fallback
+    pub confidence: Option<f64>,
     pub explanation: String,
 }
-#[derive(Deserialize,Debug)]
-pub struct ParsedSentimentLLMResponse{
+// A named entity found in the content; `kind` is PERSON, ORG, LOCATION, DATE, EVENT, PRODUCT or OTHER
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Entity {
+    pub text: String,
+    pub kind: String,
+}
+
+// Sentiment labels the structured sentiment response may use
+pub const SENTIMENT_LABELS: [&str; 3] = ["POSITIVE", "NEGATIVE", "NEUTRAL"];
+
+// Structured sentiment answer. Deserializing fails on missing or extra fields, a label
+// outside SENTIMENT_LABELS or a confidence outside 0-100 (0-1 and percentages are both
+// accepted), so a malformed answer surfaces as ScraperError::LlmParse.
+#[derive(Deserialize, Debug)]
+#[serde(try_from = "SentimentFields")]
+pub struct ParsedSentimentLLMResponse {
     pub label: String,
     pub confidence: f64,
     pub explanation: String,
 }
-// Main LLM runner struct
-pub struct LLMRunner {
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SentimentFields {
+    label: String,
+    confidence: f64,
+    explanation: String,
+}
+
+impl TryFrom<SentimentFields> for ParsedSentimentLLMResponse {
+    type Error = String;
+
+    fn try_from(fields: SentimentFields) -> Result<Self, Self::Error> {
+        let label = fields.label.trim().to_uppercase();
+        if !SENTIMENT_LABELS.contains(&label.as_str()) {
+            return Err(format!("label '{}' is not one of {}", fields.label, SENTIMENT_LABELS.join(", ")));
+        }
+        if !(0.0..=100.0).contains(&fields.confidence) {
+            return Err(format!("confidence {} is out of range", fields.confidence));
+        }
+        Ok(Self { label, confidence: fields.confidence, explanation: fields.explanation })
+    }
+}
+// OpenAI chat completions request/response structures
+#[derive(Serialize)]
+pub struct OpenAiRequest {
+    pub model: String,
+    pub messages: Vec<OpenAiMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OpenAiMessage {
+    pub role: String,
+    pub content: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OpenAiResponse {
+    pub choices: Vec<OpenAiChoice>,
+    pub usage: Option<OpenAiUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OpenAiUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OpenAiChoice {
+    pub message: OpenAiMessage,
+}
+
+// Ollama generate request/response structures
+#[derive(Serialize)]
+pub struct OllamaRequest {
+    pub model: String,
+    pub prompt: String,
+    pub stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<OllamaOptions>,
+}
+
+#[derive(Serialize)]
+pub struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OllamaResponse {
+    pub response: String,
+    pub prompt_eval_count: Option<u64>,
+    pub eval_count: Option<u64>,
+}
+
+// Default time allowed for a single LLM request
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Read a failed response into an ApiRequest error
+async fn api_error(response: reqwest::Response) -> ScraperError {
+    let status = response.status().as_u16();
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_retry_after);
+    match response.text().await {
+        Ok(body) => ScraperError::ApiRequest { status, body, retry_after },
+        Err(e) => request_error(e),
+    }
+}
+
+// Retry-After value as a wait: either a number of seconds or an HTTP date, which counts
+// as no wait once it has passed
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO))
+}
+
+// Convert reqwest failures, surfacing timeouts as ScraperError::Timeout
+fn request_error(err: reqwest::Error) -> ScraperError {
+    if err.is_timeout() {
+        ScraperError::Timeout
+    } else {
+        ScraperError::Http(err)
+    }
+}
+
+// Drop a surrounding ```json ... ``` fence that some models add around JSON answers
+fn strip_code_fences(text: &str) -> &str {
+    let trimmed = text.trim();
+    match trimmed.strip_prefix("```") {
+        Some(rest) => {
+            let rest = rest.strip_prefix("json").unwrap_or(rest);
+            rest.strip_suffix("```").unwrap_or(rest).trim()
+        }
+        None => trimmed,
+    }
+}
+
+// Models are asked for 0.0-1.0 but sometimes answer on a 0-100 scale already: values above
+// 1.0 are taken as percentages, smaller ones are scaled, and the result is kept in 0-100
+pub fn normalize_confidence(confidence: f64) -> f64 {
+    if !confidence.is_finite() {
+        return 0.0;
+    }
+    let percent = if confidence > 1.0 { confidence } else { confidence * 100.0 };
+    percent.clamp(0.0, 100.0)
+}
+
+// First number in a relevance answer such as "87", "Score: 87", "87%" or "87/100". A
+// "/N" denominator rescales the number to 0-100.
+pub fn parse_relevance_score(response: &str) -> Option<f64> {
+    let (value, rest) = leading_number(response)?;
+    let denominator = rest
+        .trim_start()
+        .strip_prefix('/')
+        .map(str::trim_start)
+        .filter(|denominator| denominator.starts_with(|c: char| c.is_ascii_digit()))
+        .and_then(leading_number)
+        .map(|(denominator, _)| denominator)
+        .filter(|denominator| *denominator > 0.0);
+    Some(match denominator {
+        Some(denominator) => value / denominator * 100.0,
+        None => value,
+    })
+}
+
+// The first run of digits (with an optional decimal part) in `text` and the text after it
+fn leading_number(text: &str) -> Option<(f64, &str)> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let digits = &text[start..];
+    let mut end = digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len());
+    if digits[end..].starts_with('.') {
+        let fraction = &digits[end + 1..];
+        let fraction_len = fraction.find(|c: char| !c.is_ascii_digit()).unwrap_or(fraction.len());
+        if fraction_len > 0 {
+            end += 1 + fraction_len;
+        }
+    }
+    let value = digits[..end].parse().ok()?;
+    Some((value, &digits[end..]))
+}
+
+// Allowed category in `answer`, ignoring case, surrounding quotes and punctuation
+fn match_category<'a>(answer: &str, categories: &[&'a str]) -> Option<&'a str> {
+    let answer = answer.trim().trim_matches(|c: char| !c.is_alphanumeric());
+    categories
+        .iter()
+        .find(|category| category.trim().eq_ignore_ascii_case(answer))
+        .copied()
+}
+
+// Build the HTTP client shared by the backends
+fn build_client(timeout: Duration) -> Result<Client, ScraperError> {
+    Ok(Client::builder().timeout(timeout).build()?)
+}
+
+// Per-request settings passed down to the backend
+#[derive(Debug, Clone, Default)]
+pub struct PromptOptions {
+    pub generation_config: Option<GenerationConfig>,
+    pub safety_settings: Vec<SafetySetting>,
+    // Standing instruction sent separately from the prompt, e.g. "Answer as a financial analyst."
+    pub system_instruction: Option<String>,
+}
+
+// Token counts reported by the backend for one request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub response_tokens: u64,
+}
+
+// Generated text plus the backend's token accounting, when it reports one
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub text: String,
+    pub usage: Option<TokenUsage>,
+}
+
+// Running totals of LLM traffic. Token counts are the ones reported by the API where
+// available and estimated from character counts otherwise.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct UsageStats {
+    pub prompt_chars: usize,
+    pub estimated_prompt_tokens: u64,
+    pub response_chars: usize,
+    pub estimated_response_tokens: u64,
+    pub request_count: usize,
+}
+
+impl UsageStats {
+    fn record(&mut self, prompt: &str, completion: &Completion) {
+        let usage = completion.usage.unwrap_or_else(|| TokenUsage {
+            prompt_tokens: estimate_tokens(prompt) as u64,
+            response_tokens: estimate_tokens(&completion.text) as u64,
+        });
+        self.prompt_chars += prompt.chars().count();
+        self.estimated_prompt_tokens += usage.prompt_tokens;
+        self.response_chars += completion.text.chars().count();
+        self.estimated_response_tokens += usage.response_tokens;
+        self.request_count += 1;
+    }
+}
+
+// Chunks of generated text from a streaming request
+pub type TextStream = BoxStream<'static, Result<String, ScraperError>>;
+
+// A backend that can answer a single prompt with text
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    async fn send_prompt(&self, prompt: &str) -> Result<String, ScraperError>;
+
+    // Send a prompt with per-request options; backends that can't honor them fall back to send_prompt
+    async fn send_prompt_with(&self, prompt: &str, options: &PromptOptions) -> Result<String, ScraperError> {
+        let _ = options;
+        self.send_prompt(prompt).await
+    }
+
+    // Like send_prompt_with, also returning token usage for backends that report it
+    async fn complete(&self, prompt: &str, options: &PromptOptions) -> Result<Completion, ScraperError> {
+        let text = self.send_prompt_with(prompt, options).await?;
+        Ok(Completion { text, usage: None })
+    }
+
+    // Stream the answer in chunks as they are generated; by default the full answer is one chunk
+    async fn send_prompt_stream(&self, prompt: &str, options: &PromptOptions) -> Result<TextStream, ScraperError> {
+        let text = self.send_prompt_with(prompt, options).await?;
+        Ok(stream::once(future::ready(Ok(text))).boxed())
+    }
+
+    // Name of the model answering prompts
+    fn model(&self) -> &str;
+
+    // Switch the model used for subsequent prompts
+    fn set_model(&mut self, model: &str);
+
+    // Send requests to another API root, such as a gateway in front of the real service
+    fn set_base_url(&mut self, base_url: &str) -> Result<(), ScraperError> {
+        let _ = base_url;
+        Err(ScraperError::Config("This LLM backend does not support a custom base URL".to_string()))
+    }
+}
+
+// Check that `base_url` is an http(s) URL, returning it without a trailing slash
+fn parse_base_url(base_url: &str) -> Result<String, ScraperError> {
+    let base_url = base_url.trim().trim_end_matches('/');
+    match url::Url::parse(base_url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.host().is_some() => {
+            Ok(base_url.to_string())
+        }
+        _ => Err(ScraperError::Config(format!("Invalid LLM base URL '{}'", base_url))),
+    }
+}
+
+// Model used by the Gemini backend unless overridden
+pub const DEFAULT_GEMINI_MODEL: &str = "gemini-pro";
+
+// Root of the Gemini REST API
+pub const GEMINI_API_BASE: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+// The first non-blank value `lookup` finds for `names`, tried in order, or ApiKeyMissing
+// listing all of them
+fn first_key(names: &[&str], lookup: impl Fn(&str) -> Option<String>) -> Result<String, ScraperError> {
+    names
+        .iter()
+        .find_map(|name| lookup(name).filter(|value| !value.trim().is_empty()))
+        .ok_or_else(|| ScraperError::ApiKeyMissing { vars: names.join(", ") })
+}
+
+// Google Gemini generateContent backend (the default)
+pub struct GeminiProvider {
     client: Client,
     api_key: String,
-    base_url: String,
+    api_base: String,
+    model: String,
 }
 
-impl LLMRunner {
-    // Initialize the LLM runner
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let api_key = env::var("LLM_API_KEY")
-            .map_err(|_| "LLM_API_KEY must be set in .env file")?;
-        
-        Ok(Self {
-            client: Client::new(),
+impl GeminiProvider {
+    pub fn new(client: Client, api_key: String) -> Self {
+        Self {
+            client,
             api_key,
-            base_url: "https://generativelanguage.googleapis.com/v1beta/models/gemini-pro:generateContent".to_string(),
-        })
+            api_base: GEMINI_API_BASE.to_string(),
+            model: DEFAULT_GEMINI_MODEL.to_string(),
+        }
     }
 
-    // Generic method to send prompts to LLM
-    pub async fn send_prompt(&self, prompt: &str) -> Result<String, Box<dyn std::error::Error>> {
-        let url = format!("{}?key={}", self.base_url, self.api_key);
-        
-        let request_body = GeminiRequest {
+    // Environment variables checked for the API key, in order
+    pub const API_KEY_VARS: [&'static str; 2] = ["GEMINI_API_KEY", "LLM_API_KEY"];
+
+    pub fn from_env(client: Client) -> Result<Self, ScraperError> {
+        let api_key = first_key(&Self::API_KEY_VARS, |name| env::var(name).ok())?;
+
+        let mut provider = Self::new(client, api_key);
+        // A gateway or compatible endpoint used instead of Google's API
+        if let Ok(base_url) = env::var("GEMINI_BASE_URL")
+            && !base_url.trim().is_empty()
+        {
+            provider.set_base_url(&base_url)?;
+        }
+        Ok(provider)
+    }
+
+    // URL of a model method such as generateContent, e.g. .../models/gemini-pro:generateContent
+    fn endpoint(&self, method: &str) -> String {
+        format!("{}/models/{}:{}", self.api_base, self.model, method)
+    }
+
+    fn request_body(prompt: &str, options: &PromptOptions) -> GeminiRequest {
+        GeminiRequest {
             contents: vec![Content {
                 parts: vec![Part {
                     text: prompt.to_string(),
                 }],
             }],
-        };
+            generation_config: options.generation_config.clone(),
+            safety_settings: options.safety_settings.clone(),
+            system_instruction: options.system_instruction.as_ref().map(|instruction| Content {
+                parts: vec![Part {
+                    text: instruction.clone(),
+                }],
+            }),
+        }
+    }
 
+    // POST a request to `url`, turning non-success statuses into ApiRequest errors. The key
+    // goes in a header rather than the query string, so it never shows up in logged URLs.
+    async fn post(&self, url: &str, request_body: &GeminiRequest) -> Result<reqwest::Response, ScraperError> {
         let response = self.client
-            .post(&url)
+            .post(url)
             .header("Content-Type", "application/json")
+            .header("x-goog-api-key", &self.api_key)
+            .json(request_body)
+            .send()
+            .await
+            .map_err(request_error)?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response).await);
+        }
+        Ok(response)
+    }
+}
+
+// Text of the first candidate, if the response has one
+fn first_candidate_text(response: &GeminiResponse) -> Option<String> {
+    response
+        .candidates
+        .first()
+        .and_then(|candidate| candidate.content.as_ref())
+        .and_then(|content| content.parts.first())
+        .map(|part| part.text.clone())
+}
+
+// Finish reasons for which Gemini withholds or cuts the answer for policy reasons
+const BLOCKED_FINISH_REASONS: &[&str] = &["SAFETY", "RECITATION", "BLOCKLIST", "PROHIBITED_CONTENT", "SPII"];
+
+// Fail on a candidate stopped by a content filter, and warn when one ran out of output
+// tokens, since its text then ends mid-sentence
+fn check_finish_reason(response: &GeminiResponse) -> Result<(), ScraperError> {
+    let Some(reason) = response.candidates.first().and_then(|candidate| candidate.finish_reason.as_deref()) else {
+        return Ok(());
+    };
+    if BLOCKED_FINISH_REASONS.contains(&reason) {
+        return Err(ScraperError::Blocked { reason: format!("response stopped for {}", reason) });
+    }
+    if reason == "MAX_TOKENS" {
+        warn!("LLM response hit the output token limit and is truncated");
+    }
+    Ok(())
+}
+
+// Turn a Gemini server-sent event stream into the text of each chunk
+fn gemini_sse_chunks<S, B>(bytes: S) -> impl Stream<Item = Result<String, ScraperError>> + Send + 'static
+where
+    S: Stream<Item = reqwest::Result<B>> + Send + 'static,
+    B: AsRef<[u8]>,
+{
+    let state = (Box::pin(bytes.fuse()), Vec::<u8>::new(), false);
+    stream::unfold(state, |(mut bytes, mut buffer, failed)| async move {
+        if failed {
+            return None;
+        }
+        loop {
+            if let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=pos).collect();
+                let line = String::from_utf8_lossy(&line);
+                let Some(data) = line.trim_end().strip_prefix("data:") else {
+                    continue;
+                };
+                match serde_json::from_str::<GeminiResponse>(data.trim()) {
+                    Ok(chunk) if let Err(e) = check_finish_reason(&chunk) => {
+                        return Some((Err(e), (bytes, buffer, true)));
+                    }
+                    Ok(chunk) => match first_candidate_text(&chunk) {
+                        Some(text) => return Some((Ok(text), (bytes, buffer, false))),
+                        None => continue,
+                    },
+                    Err(e) => {
+                        let err = ScraperError::LlmParse { message: e.to_string(), raw: data.to_string() };
+                        return Some((Err(err), (bytes, buffer, true)));
+                    }
+                }
+            }
+            match bytes.next().await {
+                Some(Ok(chunk)) => buffer.extend_from_slice(chunk.as_ref()),
+                Some(Err(e)) => return Some((Err(request_error(e)), (bytes, buffer, true))),
+                None if buffer.is_empty() => return None,
+                // Flush a final event that wasn't newline-terminated
+                None => buffer.push(b'\n'),
+            }
+        }
+    })
+}
+
+#[async_trait]
+impl LlmProvider for GeminiProvider {
+    async fn send_prompt(&self, prompt: &str) -> Result<String, ScraperError> {
+        self.send_prompt_with(prompt, &PromptOptions::default()).await
+    }
+
+    async fn send_prompt_with(&self, prompt: &str, options: &PromptOptions) -> Result<String, ScraperError> {
+        Ok(self.complete(prompt, options).await?.text)
+    }
+
+    async fn complete(&self, prompt: &str, options: &PromptOptions) -> Result<Completion, ScraperError> {
+        let response = self.post(&self.endpoint("generateContent"), &Self::request_body(prompt, options)).await?;
+
+        let gemini_response: GeminiResponse = response.json().await.map_err(request_error)?;
+        check_finish_reason(&gemini_response)?;
+        if let Some(text) = first_candidate_text(&gemini_response) {
+            let usage = gemini_response.usage_metadata.map(|usage| TokenUsage {
+                prompt_tokens: usage.prompt_token_count,
+                response_tokens: usage.candidates_token_count,
+            });
+            return Ok(Completion { text, usage });
+        }
+
+        match gemini_response.prompt_feedback.and_then(|feedback| feedback.block_reason) {
+            Some(reason) => Err(ScraperError::Blocked { reason }),
+            None => Err(ScraperError::EmptyResponse),
+        }
+    }
+
+    async fn send_prompt_stream(&self, prompt: &str, options: &PromptOptions) -> Result<TextStream, ScraperError> {
+        let url = format!("{}?alt=sse", self.endpoint("streamGenerateContent"));
+        let response = self.post(&url, &Self::request_body(prompt, options)).await?;
+
+        Ok(gemini_sse_chunks(response.bytes_stream()).boxed())
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn set_model(&mut self, model: &str) {
+        self.model = model.to_string();
+    }
+
+    fn set_base_url(&mut self, base_url: &str) -> Result<(), ScraperError> {
+        self.api_base = parse_base_url(base_url)?;
+        Ok(())
+    }
+}
+
+// OpenAI-compatible chat completions backend
+pub struct OpenAiProvider {
+    client: Client,
+    api_key: String,
+    base_url: String,
+    model: String,
+}
+
+impl OpenAiProvider {
+    pub fn new(client: Client, api_key: String, model: String) -> Self {
+        Self {
+            client,
+            api_key,
+            base_url: "https://api.openai.com/v1/chat/completions".to_string(),
+            model,
+        }
+    }
+
+    // Environment variables checked for the API key, in order
+    pub const API_KEY_VARS: [&'static str; 2] = ["OPENAI_API_KEY", "LLM_API_KEY"];
+
+    pub fn from_env(client: Client) -> Result<Self, ScraperError> {
+        let api_key = first_key(&Self::API_KEY_VARS, |name| env::var(name).ok())?;
+        let model = env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string());
+
+        Ok(Self::new(client, api_key, model))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiProvider {
+    async fn send_prompt(&self, prompt: &str) -> Result<String, ScraperError> {
+        self.send_prompt_with(prompt, &PromptOptions::default()).await
+    }
+
+    async fn send_prompt_with(&self, prompt: &str, options: &PromptOptions) -> Result<String, ScraperError> {
+        Ok(self.complete(prompt, options).await?.text)
+    }
+
+    async fn complete(&self, prompt: &str, options: &PromptOptions) -> Result<Completion, ScraperError> {
+        let config = options.generation_config.clone().unwrap_or_default();
+        let mut messages = Vec::new();
+        if let Some(instruction) = &options.system_instruction {
+            messages.push(OpenAiMessage {
+                role: "system".to_string(),
+                content: instruction.clone(),
+            });
+        }
+        messages.push(OpenAiMessage {
+            role: "user".to_string(),
+            content: prompt.to_string(),
+        });
+        let request_body = OpenAiRequest {
+            model: self.model.clone(),
+            messages,
+            temperature: config.temperature,
+            top_p: config.top_p,
+            max_tokens: config.max_output_tokens,
+        };
+
+        let response = self.client
+            .post(&self.base_url)
+            .bearer_auth(&self.api_key)
             .json(&request_body)
             .send()
-            .await?;
+            .await
+            .map_err(request_error)?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response).await);
+        }
+
+        let openai_response: OpenAiResponse = response.json().await.map_err(request_error)?;
+        let usage = openai_response.usage.map(|usage| TokenUsage {
+            prompt_tokens: usage.prompt_tokens,
+            response_tokens: usage.completion_tokens,
+        });
+        openai_response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| Completion { text: choice.message.content, usage })
+            .ok_or(ScraperError::EmptyResponse)
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn set_model(&mut self, model: &str) {
+        self.model = model.to_string();
+    }
+}
+
+// Local Ollama backend
+pub struct OllamaProvider {
+    client: Client,
+    base_url: String,
+    model: String,
+}
+
+impl OllamaProvider {
+    pub fn new(client: Client, host: &str, model: String) -> Self {
+        Self {
+            client,
+            base_url: format!("{}/api/generate", host.trim_end_matches('/')),
+            model,
+        }
+    }
+
+    pub fn from_env(client: Client) -> Self {
+        let host = env::var("OLLAMA_HOST").unwrap_or_else(|_| "http://localhost:11434".to_string());
+        let model = env::var("OLLAMA_MODEL").unwrap_or_else(|_| "llama3".to_string());
+
+        Self::new(client, &host, model)
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    async fn send_prompt(&self, prompt: &str) -> Result<String, ScraperError> {
+        self.send_prompt_with(prompt, &PromptOptions::default()).await
+    }
+
+    async fn send_prompt_with(&self, prompt: &str, options: &PromptOptions) -> Result<String, ScraperError> {
+        Ok(self.complete(prompt, options).await?.text)
+    }
+
+    async fn complete(&self, prompt: &str, options: &PromptOptions) -> Result<Completion, ScraperError> {
+        let request_body = OllamaRequest {
+            model: self.model.clone(),
+            prompt: prompt.to_string(),
+            stream: false,
+            system: options.system_instruction.clone(),
+            options: options.generation_config.as_ref().map(|config| OllamaOptions {
+                temperature: config.temperature,
+                top_p: config.top_p,
+                top_k: config.top_k,
+                num_predict: config.max_output_tokens,
+            }),
+        };
+
+        let response = self.client
+            .post(&self.base_url)
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(request_error)?;
 
-        if response.status().is_success() {
-            let gemini_response: GeminiResponse = response.json().await?;
-            
-            if let Some(candidate) = gemini_response.candidates.first() {
-                if let Some(part) = candidate.content.parts.first() {
-                    return Ok(part.text.clone());
+        if !response.status().is_success() {
+            return Err(api_error(response).await);
+        }
+
+        let ollama_response: OllamaResponse = response.json().await.map_err(request_error)?;
+        let usage = match (ollama_response.prompt_eval_count, ollama_response.eval_count) {
+            (Some(prompt_tokens), Some(response_tokens)) => Some(TokenUsage { prompt_tokens, response_tokens }),
+            _ => None,
+        };
+        Ok(Completion { text: ollama_response.response, usage })
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn set_model(&mut self, model: &str) {
+        self.model = model.to_string();
+    }
+}
+
+// Offline backend that answers with canned responses, for tests and trying out prompts
+// without an API key
+pub struct MockProvider {
+    responses: std::sync::Mutex<VecDeque<String>>,
+    fallback: Option<String>,
+    prompts: Arc<std::sync::Mutex<Vec<String>>>,
+    model: String,
+}
+
+impl MockProvider {
+    // Answer prompts with `responses` in order; once they run out, requests fail with
+    // EmptyResponse unless a fallback is set
+    pub fn new<S: Into<String>>(responses: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            responses: std::sync::Mutex::new(responses.into_iter().map(Into::into).collect()),
+            fallback: None,
+            prompts: Arc::new(std::sync::Mutex::new(Vec::new())),
+            model: "mock".to_string(),
+        }
+    }
+
+    // Answer every prompt with `response` once the queued responses are used up
+    pub fn with_fallback(mut self, response: &str) -> Self {
+        self.fallback = Some(response.to_string());
+        self
+    }
+
+    // Handle to the prompts received so far, still readable after the provider has been
+    // moved into an LLMRunner
+    pub fn prompts(&self) -> Arc<std::sync::Mutex<Vec<String>>> {
+        Arc::clone(&self.prompts)
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MockProvider {
+    async fn send_prompt(&self, prompt: &str) -> Result<String, ScraperError> {
+        self.prompts.lock().unwrap().push(prompt.to_string());
+        let next = self.responses.lock().unwrap().pop_front();
+        next.or_else(|| self.fallback.clone()).ok_or(ScraperError::EmptyResponse)
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn set_model(&mut self, model: &str) {
+        self.model = model.to_string();
+    }
+}
+
+// Characters of page content sent with each prompt unless configured otherwise
+pub const DEFAULT_MAX_CONTENT_CHARS: usize = 4000;
+
+// Sentences per chunk summary in summarize_long's map step
+const CHUNK_SUMMARY_SENTENCES: u32 = 5;
+
+// Size of the pieces long text is translated in
+pub const TRANSLATE_CHUNK_CHARS: usize = 4000;
+
+// Language code used when detection fails
+pub const UNKNOWN_LANGUAGE: &str = "unknown";
+
+// Categories classify_content chooses from unless given its own list
+pub const DEFAULT_CATEGORIES: &[&str] = &[
+    "Technology",
+    "News",
+    "Business",
+    "Education",
+    "Entertainment",
+    "Sports",
+    "Health",
+    "Science",
+    "Politics",
+    "Lifestyle",
+    "Other",
+];
+
+// What answer_question replies when the content doesn't answer the question
+pub const NOT_FOUND_ANSWER: &str = "Not found in content";
+
+// Default retry policy for transient failures
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+pub const DEFAULT_RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+// Longest wait before a retry. A server asking for more (Retry-After) fails the request instead.
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+// How analyze_web_content builds a ContentAnalysis
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnalysisMode {
+    // One prompt asking for every section, parsed from labeled lines
+    #[default]
+    Single,
+    // Separate summary, sentiment, topic and category calls run concurrently
+    Composed,
+}
+
+// Main LLM runner struct. It is Send + Sync (rate limiting and usage counters sit behind
+// mutexes), so one runner can be shared across tokio tasks through an Arc.
+pub struct LLMRunner {
+    provider: Box<dyn LlmProvider>,
+    max_retries: u32,
+    retry_base_delay: Duration,
+    min_interval: Duration,
+    last_call: Mutex<Option<Instant>>,
+    generation_config: Option<GenerationConfig>,
+    safety_settings: Vec<SafetySetting>,
+    system_instruction: Option<String>,
+    prompt_template: PromptTemplate,
+    analysis_mode: AnalysisMode,
+    include_raw: bool,
+    max_content_chars: usize,
+    cache: Option<DiskCache>,
+    usage: std::sync::Mutex<UsageStats>,
+}
+
+// Fails to compile if a new field makes LLMRunner unsafe to share between tasks
+fn assert_send_sync<T: Send + Sync>() {}
+const _: fn() = assert_send_sync::<LLMRunner>;
+
+impl LLMRunner {
+    // Initialize the LLM runner with the default Gemini backend
+    pub fn new() -> Result<Self, ScraperError> {
+        Self::with_timeout(DEFAULT_TIMEOUT)
+    }
+
+    // Initialize the default Gemini backend with a custom request timeout
+    pub fn with_timeout(timeout: Duration) -> Result<Self, ScraperError> {
+        Ok(Self::with_provider(GeminiProvider::from_env(build_client(timeout)?)?))
+    }
+
+    // Initialize the LLM runner with any backend
+    pub fn with_provider<P: LlmProvider + 'static>(provider: P) -> Self {
+        Self {
+            provider: Box::new(provider),
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_base_delay: DEFAULT_RETRY_BASE_DELAY,
+            min_interval: Duration::ZERO,
+            last_call: Mutex::new(None),
+            generation_config: None,
+            safety_settings: Vec::new(),
+            system_instruction: None,
+            prompt_template: PromptTemplate::default(),
+            analysis_mode: AnalysisMode::Single,
+            include_raw: false,
+            max_content_chars: DEFAULT_MAX_CONTENT_CHARS,
+            cache: None,
+            usage: std::sync::Mutex::new(UsageStats::default()),
+        }
+    }
+
+    // Retry transient failures up to `max` times, doubling the delay from `base` each attempt
+    pub fn with_retries(mut self, max: u32, base: Duration) -> Self {
+        self.max_retries = max;
+        self.retry_base_delay = base;
+        self
+    }
+
+    // Pick the backend from LLM_PROVIDER (gemini, openai or ollama), defaulting to gemini
+    pub fn from_env() -> Result<Self, ScraperError> {
+        Self::from_env_with_timeout(DEFAULT_TIMEOUT)
+    }
+
+    // Same as from_env, with a custom request timeout
+    pub fn from_env_with_timeout(timeout: Duration) -> Result<Self, ScraperError> {
+        let provider_name = env::var("LLM_PROVIDER").unwrap_or_else(|_| "gemini".to_string());
+        let client = build_client(timeout)?;
+
+        match provider_name.trim().to_lowercase().as_str() {
+            "gemini" => Ok(Self::with_provider(GeminiProvider::from_env(client)?)),
+            "openai" => Ok(Self::with_provider(OpenAiProvider::from_env(client)?)),
+            "ollama" => Ok(Self::with_provider(OllamaProvider::from_env(client))),
+            other => Err(ScraperError::Config(format!(
+                "Unsupported LLM_PROVIDER '{}' (expected gemini, openai or ollama)",
+                other
+            ))),
+        }
+    }
+
+    // Use a different model, e.g. "gemini-1.5-flash" for cheap bulk work
+    pub fn with_model(mut self, model: &str) -> Result<Self, ScraperError> {
+        let model = model.trim();
+        if model.is_empty() {
+            return Err(ScraperError::Config("Model name must not be empty".to_string()));
+        }
+        self.provider.set_model(model);
+        Ok(self)
+    }
+
+    // Send requests to `base_url` (e.g. https://gateway.corp/gemini/v1beta) instead of the
+    // backend's public API
+    pub fn with_base_url(mut self, base_url: &str) -> Result<Self, ScraperError> {
+        self.provider.set_base_url(base_url)?;
+        Ok(self)
+    }
+
+    // Default sampling/length settings applied to every prompt
+    pub fn with_generation_config(mut self, config: GenerationConfig) -> Self {
+        self.generation_config = Some(config);
+        self
+    }
+
+    // Gemini safety thresholds sent with every prompt
+    pub fn with_safety_settings(mut self, settings: Vec<SafetySetting>) -> Self {
+        self.safety_settings = settings;
+        self
+    }
+
+    // Instruction applied to every prompt, such as a persona ("Answer as a financial analyst.")
+    pub fn with_system_instruction(mut self, instruction: &str) -> Self {
+        let instruction = instruction.trim();
+        self.system_instruction = (!instruction.is_empty()).then(|| instruction.to_string());
+        self
+    }
+
+    // Prompt used by analyze_web_content, in place of the built-in SUMMARY/SENTIMENT/TOPICS/CATEGORY one
+    pub fn with_prompt_template(mut self, template: PromptTemplate) -> Self {
+        self.prompt_template = template;
+        self
+    }
+
+    // Choose between the single-prompt and composed analysis
+    pub fn with_analysis_mode(mut self, mode: AnalysisMode) -> Self {
+        self.analysis_mode = mode;
+        self
+    }
+
+    // Keep the full model response in ContentAnalysis::raw, for debugging prompts and parsing
+    pub fn include_raw(mut self, include_raw: bool) -> Self {
+        self.include_raw = include_raw;
+        self
+    }
+
+    // Send at most `max_chars` characters of content with each prompt; longer content is cut.
+    // summarize_long also splits its input into windows of this size.
+    pub fn with_max_content_chars(mut self, max_chars: usize) -> Self {
+        self.max_content_chars = max_chars.max(1);
+        self
+    }
+
+    // Reuse responses stored under `dir` for identical prompts younger than `ttl`
+    pub fn with_cache(mut self, dir: impl AsRef<Path>, ttl: Duration) -> Result<Self, ScraperError> {
+        self.cache = Some(DiskCache::new(dir, ttl)?);
+        Ok(self)
+    }
+
+    // Space consecutive requests at least `interval` apart to stay under provider rate limits
+    pub fn with_min_interval(mut self, interval: Duration) -> Self {
+        self.min_interval = interval;
+        self
+    }
+
+    // Totals for every request sent to the backend so far (cached answers aren't counted)
+    pub fn usage(&self) -> UsageStats {
+        *self.usage.lock().unwrap()
+    }
+
+    // Wait until the minimum interval since the previous request has passed
+    async fn wait_for_rate_limit(&self) {
+        let mut last_call = self.last_call.lock().await;
+        if let Some(last) = *last_call {
+            let elapsed = last.elapsed();
+            if elapsed < self.min_interval {
+                sleep(self.min_interval - elapsed).await;
+            }
+        }
+        *last_call = Some(Instant::now());
+    }
+
+    // Combine per-call generation settings with the runner-wide defaults
+    fn prompt_options(&self, config: GenerationConfig) -> PromptOptions {
+        let config = match &self.generation_config {
+            Some(defaults) => config.or(defaults),
+            None => config,
+        };
+        PromptOptions {
+            generation_config: (config != GenerationConfig::default()).then_some(config),
+            safety_settings: self.safety_settings.clone(),
+            system_instruction: self.system_instruction.clone(),
+        }
+    }
+
+    // Generic method to send prompts to LLM, retrying transient failures after the server's
+    // Retry-After wait or, without one, with exponential backoff
+    pub async fn send_prompt(&self, prompt: &str) -> Result<String, ScraperError> {
+        self.send_prompt_with_config(prompt, GenerationConfig::default()).await
+    }
+
+    // Send a prompt with generation settings layered over the runner defaults
+    #[instrument(level = "debug", skip_all, fields(model = self.provider.model()))]
+    pub async fn send_prompt_with_config(
+        &self,
+        prompt: &str,
+        config: GenerationConfig,
+    ) -> Result<String, ScraperError> {
+        let options = self.prompt_options(config);
+
+        // Responses are keyed by model, options and prompt so a change to any of them misses
+        let cache = self.cache.as_ref().map(|cache| {
+            let options_json = serde_json::to_string(&(
+                &options.generation_config,
+                &options.safety_settings,
+                &options.system_instruction,
+            ))
+            .unwrap_or_default();
+            (cache, DiskCache::key(&[self.provider.model(), &options_json, prompt]))
+        });
+        if let Some((cache, key)) = &cache
+            && let Some(text) = cache.get(key)
+        {
+            debug!("Using cached LLM response");
+            return Ok(text);
+        }
+
+        let mut attempt = 0;
+        loop {
+            self.wait_for_rate_limit().await;
+            match self.provider.complete(prompt, &options).await {
+                Ok(completion) => {
+                    self.usage.lock().unwrap().record(prompt, &completion);
+                    let text = completion.text;
+                    if let Some((cache, key)) = &cache
+                        && let Err(e) = cache.put(key, &text)
+                    {
+                        warn!("Failed to write LLM cache entry: {}", e);
+                    }
+                    return Ok(text);
+                }
+                Err(e) if attempt < self.max_retries && e.is_retryable() => {
+                    let delay = match e.retry_after() {
+                        Some(delay) if delay > MAX_RETRY_DELAY => {
+                            warn!("LLM request failed ({}) and the server asked to wait {:?}; not retrying", e, delay);
+                            return Err(e);
+                        }
+                        Some(delay) => delay,
+                        None => (self.retry_base_delay * 2u32.saturating_pow(attempt)).min(MAX_RETRY_DELAY),
+                    };
+                    warn!(
+                        "LLM request failed ({}), retrying in {:?} (attempt {}/{})",
+                        e, delay, attempt + 1, self.max_retries
+                    );
+                    sleep(delay).await;
+                    attempt += 1;
                 }
+                Err(e) => return Err(e),
             }
-        } else {
-            let error_text = response.text().await?;
-            return Err(format!("API request failed: {}", error_text).into());
         }
-        
-        Err("No response from LLM".into())
+    }
+
+    // Ask for a JSON answer (optionally constrained by a response schema) and deserialize it.
+    // Models that ignore the mime type may still wrap the JSON in prose or code fences, so
+    // parse failures come back as LlmParse with the raw text for callers to fall back on.
+    pub async fn send_prompt_json<T: DeserializeOwned>(
+        &self,
+        prompt: &str,
+        schema: Option<serde_json::Value>,
+    ) -> Result<T, ScraperError> {
+        let config = GenerationConfig {
+            response_mime_type: Some("application/json".to_string()),
+            response_schema: schema,
+            ..GenerationConfig::default()
+        };
+        let raw = self.send_prompt_with_config(prompt, config).await?;
+
+        serde_json::from_str(strip_code_fences(&raw)).map_err(|e| ScraperError::LlmParse {
+            message: e.to_string(),
+            raw,
+        })
     }
 
     // Analyze web content comprehensively
@@ -123,49 +1245,57 @@ impl LLMRunner {
         title: &str, 
         content: &str, 
         url: &str
-    ) -> Result<ContentAnalysis, Box<dyn std::error::Error>> {
-        
+    ) -> Result<ContentAnalysis, ScraperError> {
+        self.analyze_web_content_with_context(title, content, url, None).await
+    }
+
+    // Same as analyze_web_content, with optional extra context such as the page's meta description.
+    // In composed mode this runs analyze_web_content_composed, which ignores the context.
+    pub async fn analyze_web_content_with_context(
+        &self,
+        title: &str,
+        content: &str,
+        url: &str,
+        context: Option<&str>,
+    ) -> Result<ContentAnalysis, ScraperError> {
+        if self.analysis_mode == AnalysisMode::Composed {
+            return self.analyze_web_content_composed(title, content).await;
+        }
+
         // Truncate content to avoid API limits (Gemini has token limits)
-        let truncated_content = if content.len() > 3000 {
-            &content[..3000]
-        } else {
-            content
-        };
+        let truncated_content = self.truncate_content(content);
+        let prompt = self.prompt_template.render(url, title, context, truncated_content);
 
-        let prompt = format!(
-            "Analyze this web content and provide structured analysis:\n\n\
-            URL: {}\n\
-            Title: {}\n\
-            Content: {}\n\n\
-            Please provide analysis in this exact format:\n\
-            SUMMARY: [2-3 sentence summary]\n\
-            SENTIMENT: [POSITIVE/NEGATIVE/NEUTRAL with brief explanation]\n\
-            TOPICS: [comma-separated key topics/themes]\n\
-            CATEGORY: [main category like Technology, News, Business, Education, etc.]\n\n\
-            Be concise and accurate.",
-            url, title, truncated_content
-        );
+        let mut response = self.send_prompt(&prompt).await?;
 
-        let response = self.send_prompt(&prompt).await?;
-        
-        // Parse structured response
-        let mut summary = String::new();
-        let mut sentiment = String::new();
-        let mut topics = String::new();
-        let mut category = String::new();
-
-        for line in response.lines() {
-            let line = line.trim();
-            if line.starts_with("SUMMARY:") {
-                summary = line.replace("SUMMARY:", "").trim().to_string();
-            } else if line.starts_with("SENTIMENT:") {
-                sentiment = line.replace("SENTIMENT:", "").trim().to_string();
-            } else if line.starts_with("TOPICS:") {
-                topics = line.replace("TOPICS:", "").trim().to_string();
-            } else if line.starts_with("CATEGORY:") {
-                category = line.replace("CATEGORY:", "").trim().to_string();
+        // Parse structured response, using whatever sections the template declares
+        let labels: Vec<&str> = self.prompt_template.labels().iter().map(String::as_str).collect();
+        let mut sections = parse_labeled_sections(&response, &labels);
+
+        // Ask once more, insisting on the format, before settling for the raw-text fallback
+        let unparsed = |sections: &HashMap<String, String>| {
+            !labels.is_empty()
+                && (sections.is_empty() || (labels.contains(&"SUMMARY") && !sections.contains_key("SUMMARY")))
+        };
+        if unparsed(&sections) {
+            warn!("Analysis response was not in the expected format, retrying with a format reminder");
+            let retry_prompt = format!(
+                "{}\n\nIMPORTANT: Respond ONLY in the exact format above, one line per section \
+                starting with its label and a colon ({}), with no other prose.",
+                prompt,
+                labels.join(", ")
+            );
+            let retry_response = self.send_prompt(&retry_prompt).await?;
+            let retry_sections = parse_labeled_sections(&retry_response, &labels);
+            if !unparsed(&retry_sections) {
+                response = retry_response;
+                sections = retry_sections;
             }
         }
+        let mut summary = sections.remove("SUMMARY").unwrap_or_default();
+        let mut sentiment = sections.remove("SENTIMENT").unwrap_or_default();
+        let mut topics = sections.remove("TOPICS").unwrap_or_default();
+        let mut category = sections.remove("CATEGORY").unwrap_or_default();
 
         // Fallback to raw response if parsing fails
         if summary.is_empty() {
@@ -186,11 +1316,55 @@ impl LLMRunner {
             sentiment,
             key_topics: topics,
             category,
+            language: detect_language(content).unwrap_or(UNKNOWN_LANGUAGE).to_string(),
+            extra_sections: sections.into_iter().collect(),
+            raw: self.include_raw.then_some(response),
         })
     }
 
+    // Build a ContentAnalysis from summarize_content, analyze_sentiment, extract_topics and
+    // classify_content, run concurrently. Four calls instead of one, but each answer has a
+    // simple shape, so nothing falls back to the raw response.
+    pub async fn analyze_web_content_composed(&self, title: &str, content: &str) -> Result<ContentAnalysis, ScraperError> {
+        let (summary, sentiment, topics, category) = tokio::join!(
+            self.summarize_content(content, 3),
+            self.analyze_sentiment(self.truncate_content(content)),
+            self.extract_topics(content, 5),
+            self.classify_content(title, content, &[]),
+        );
+        let sentiment = sentiment?;
+
+        Ok(ContentAnalysis {
+            summary: summary?.text.trim().to_string(),
+            sentiment: format!("{} - {}", sentiment.label, sentiment.explanation.trim()),
+            key_topics: topics?
+                .iter()
+                .map(|topic| topic.trim_start_matches(['-', '*', ' ']))
+                .collect::<Vec<_>>()
+                .join(", "),
+            category: category?,
+            language: detect_language(content).unwrap_or(UNKNOWN_LANGUAGE).to_string(),
+            extra_sections: BTreeMap::new(),
+            // Each part comes from its own call, so there is no single response to keep
+            raw: None,
+        })
+    }
+
+    // Pick the analyses to run on a page, e.g.
+    // `runner.analysis().summary().topics(5).run(title, content, url).await`
+    pub fn analysis(&self) -> AnalysisBuilder<'_> {
+        AnalysisBuilder {
+            runner: self,
+            summary: false,
+            sentiment: false,
+            topics: None,
+            category: false,
+            relevance: None,
+        }
+    }
+
     // Specific sentiment analysis
-    pub async fn analyze_sentiment(&self, text: &str) -> Result<SentimentResult, Box<dyn std::error::Error>> {
+    pub async fn analyze_sentiment(&self, text: &str) -> Result<SentimentResult, ScraperError> {
         let prompt = format!(
             "Analyze the sentiment of the following text. Return your analysis as a JSON object \
             with three keys: \"label\" (string: \"POSITIVE\", \"NEGATIVE\", or \"NEUTRAL\"), \
@@ -199,18 +1373,35 @@ impl LLMRunner {
             Text: \"{}\"",
             text
         );
-        let llm_response_text = self.send_prompt(&prompt).await?;
+        let schema = serde_json::json!({
+            "type": "OBJECT",
+            "properties": {
+                "label": { "type": "STRING", "enum": SENTIMENT_LABELS },
+                "confidence": { "type": "NUMBER" },
+                "explanation": { "type": "STRING" }
+            },
+            "required": ["label", "confidence", "explanation"]
+        });
 
-        // Try to parse structured JSON response
-        match serde_json::from_str::<ParsedSentimentLLMResponse>(&llm_response_text) {
+        let mut parsed = self.send_prompt_json::<ParsedSentimentLLMResponse>(&prompt, Some(schema.clone())).await;
+        if let Err(ScraperError::LlmParse { message, .. }) = &parsed {
+            warn!("Sentiment response was invalid ({}), retrying with a format reminder", message);
+            let retry_prompt = format!(
+                "{}\n\nIMPORTANT: Respond ONLY with the JSON object, with exactly the keys label, \
+                confidence and explanation.",
+                prompt
+            );
+            parsed = self.send_prompt_json::<ParsedSentimentLLMResponse>(&retry_prompt, Some(schema)).await;
+        }
+        match parsed {
             Ok(parsed) => Ok(SentimentResult {
                 label: parsed.label,
-                confidence: parsed.confidence * 100.0, // Assuming LLM gives 0.0-1.0
+                confidence: Some(normalize_confidence(parsed.confidence)),
                 explanation: parsed.explanation,
             }),
-            Err(e) => {
-                eprintln!("Failed to parse sentiment JSON from LLM: {}. Raw response: {}", e, llm_response_text);
-                // Fallback to simpler parsing or default
+            Err(ScraperError::LlmParse { message, raw: llm_response_text }) => {
+                warn!("Failed to parse sentiment JSON from LLM: {}. Raw response: {}", message, llm_response_text);
+                // Fallback for models that ignore responseMimeType
                 Ok(SentimentResult {
                     label: if llm_response_text.to_lowercase().contains("positive") {
                         "POSITIVE".to_string()
@@ -219,32 +1410,104 @@ impl LLMRunner {
                     } else {
                         "NEUTRAL".to_string()
                     },
-                    confidence: 50.0,
+                    confidence: None,
                     explanation: llm_response_text, // Or a generic "Could not parse detailed sentiment"
                 })
             }
+            Err(e) => Err(e),
         }
     }
 
     // Summarize content
-    pub async fn summarize_content(&self, content: &str, max_sentences: u32) -> Result<String, Box<dyn std::error::Error>> {
-        let prompt = format!(
+    // When the answer is off by more than one sentence, ask once more for the exact count and
+    // keep whichever answer came closer
+    pub async fn summarize_content(&self, content: &str, max_sentences: u32) -> Result<Summary, ScraperError> {
+        let prompt = self.summary_prompt(content, max_sentences);
+        let summary = Summary::new(self.send_prompt(&prompt).await?);
+        let target = max_sentences as usize;
+        if summary.sentence_count.abs_diff(target) <= 1 {
+            return Ok(summary);
+        }
+
+        warn!(
+            "Summary has {} sentences instead of {}, retrying",
+            summary.sentence_count, max_sentences
+        );
+        let retry_prompt = format!(
+            "{}\n\nIMPORTANT: Your answer must be exactly {} sentences long, no more and no fewer.",
+            prompt, max_sentences
+        );
+        let retry = Summary::new(self.send_prompt(&retry_prompt).await?);
+        Ok(if retry.sentence_count.abs_diff(target) < summary.sentence_count.abs_diff(target) {
+            retry
+        } else {
+            summary
+        })
+    }
+
+    // Summarize content of any length: split it into max_content_chars windows, summarize
+    // each one, then summarize the combined chunk summaries (repeating while they are still
+    // too long for one request)
+    pub async fn summarize_long(&self, content: &str, max_sentences: u32) -> Result<Summary, ScraperError> {
+        let mut text = content.trim().to_string();
+        loop {
+            let length = text.chars().count();
+            if length <= self.max_content_chars {
+                break;
+            }
+            let chunks = chunk_text(&text, self.max_content_chars);
+            let summaries = future::try_join_all(
+                chunks.iter().map(|chunk| self.summarize_content(chunk, CHUNK_SUMMARY_SENTENCES)),
+            )
+            .await?;
+            text = summaries.into_iter().map(|summary| summary.text).collect::<Vec<_>>().join("\n\n");
+            // Stop reducing if the summaries didn't get any shorter than their input
+            if text.chars().count() >= length {
+                break;
+            }
+        }
+        self.summarize_content(&text, max_sentences).await
+    }
+
+    fn summary_prompt(&self, content: &str, max_sentences: u32) -> String {
+        format!(
             "Summarize the following content in exactly {} sentences. \
             Focus on the most important information:\n\n{}",
             max_sentences,
-            if content.len() > 4000 { &content[..4000] } else { content }
-        );
+            self.truncate_content(content)
+        )
+    }
 
-        self.send_prompt(&prompt).await
+    // The part of `content` that fits in one prompt
+    pub fn truncate_content<'a>(&self, content: &'a str) -> &'a str {
+        truncate_chars(content, self.max_content_chars)
+    }
+
+    // Summarize content, yielding text chunks as the model generates them
+    pub fn summarize_content_stream<'a>(
+        &'a self,
+        content: &str,
+        max_sentences: u32,
+    ) -> impl Stream<Item = Result<String, ScraperError>> + 'a {
+        let prompt = self.summary_prompt(content, max_sentences);
+        stream::once(async move {
+            self.wait_for_rate_limit().await;
+            let options = self.prompt_options(GenerationConfig::default());
+            self.provider.send_prompt_stream(&prompt, &options).await
+        })
+        .flat_map(|result| match result {
+            Ok(chunks) => chunks,
+            Err(e) => stream::once(future::ready(Err(e))).boxed(),
+        })
     }
 
     // Extract key topics/themes
-    pub async fn extract_topics(&self, content: &str, max_topics: u32) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    pub async fn extract_topics(&self, content: &str, max_topics: u32) -> Result<Vec<String>, ScraperError> {
         let prompt = format!(
             "Extract the top {} key topics or themes from this content. \
             Return only the topics, one per line:\n\n{}",
             max_topics,
-            if content.len() > 4000 { &content[..4000] } else { content }
+            self.truncate_content(content)
         );
 
         let response = self.send_prompt(&prompt).await?;
@@ -259,24 +1522,144 @@ impl LLMRunner {
         Ok(topics)
     }
 
-    // Classify content category
-    pub async fn classify_content(&self, title: &str, content: &str) -> Result<String, Box<dyn std::error::Error>> {
+    // ISO 639-1 code of the content's language. Detected locally when possible; only
+    // ambiguous text costs an LLM call. Returns "unknown" if neither gives a usable code.
+    pub async fn detect_language(&self, content: &str) -> Result<String, ScraperError> {
+        if let Some(code) = detect_language(content) {
+            return Ok(code.to_string());
+        }
+
+        let prompt = format!(
+            "What language is this text written in? \
+            Reply with only its two-letter ISO 639-1 code (e.g. en, fr, ja):\n\n{}",
+            truncate_to_tokens(content, 250)
+        );
+        let config = GenerationConfig {
+            temperature: Some(0.0),
+            ..GenerationConfig::default()
+        };
+        let reply = self.send_prompt_with_config(&prompt, config).await?;
+        let code = reply.trim().trim_matches(|c: char| !c.is_ascii_alphabetic()).to_lowercase();
+        if code.len() == 2 && code.chars().all(|c| c.is_ascii_lowercase()) {
+            Ok(code)
+        } else {
+            Ok(UNKNOWN_LANGUAGE.to_string())
+        }
+    }
+
+    // Translate `text` into `target_lang` (a language name or ISO code). Long text is
+    // translated in TRANSLATE_CHUNK_CHARS pieces that are joined back together.
+    pub async fn translate(&self, text: &str, target_lang: &str) -> Result<String, ScraperError> {
+        let mut translated = Vec::new();
+        for chunk in chunk_text(text, TRANSLATE_CHUNK_CHARS) {
+            let prompt = format!(
+                "Translate the following text into {}. \
+                Keep the meaning, names and formatting; \
+                reply with only the translation:\n\n{}",
+                target_lang.trim(),
+                chunk
+            );
+            translated.push(self.send_prompt(&prompt).await?.trim().to_string());
+        }
+        Ok(translated.join("\n\n"))
+    }
+
+    // Answer `question` using only `content`; the reply is NOT_FOUND_ANSWER when the content
+    // doesn't contain the answer
+    pub async fn answer_question(&self, content: &str, question: &str) -> Result<String, ScraperError> {
+        let prompt = format!(
+            "Answer the question using only the content below. \
+            Do not use outside knowledge. If the content does not contain the answer, \
+            reply with exactly \"{}\".\n\n\
+            Content: {}\n\n\
+            Question: {}\n\n\
+            Answer:",
+            NOT_FOUND_ANSWER,
+            self.truncate_content(content),
+            question.trim()
+        );
+
+        let answer = self.send_prompt(&prompt).await?;
+        Ok(answer.trim().to_string())
+    }
+
+    // Extract named entities (people, organizations, places, dates, ...) as structured JSON
+    pub async fn extract_entities(&self, content: &str) -> Result<Vec<Entity>, ScraperError> {
+        let prompt = format!(
+            "Extract the named entities mentioned in this content. \
+            For each entity give its text exactly as written and its kind, one of: \
+            PERSON, ORG, LOCATION, DATE, EVENT, PRODUCT, OTHER. \
+            Respond with a JSON array of objects with \"text\" and \"kind\" keys, \
+            listing each entity once:\n\n{}",
+            self.truncate_content(content)
+        );
+        let schema = serde_json::json!({
+            "type": "ARRAY",
+            "items": {
+                "type": "OBJECT",
+                "properties": {
+                    "text": { "type": "STRING" },
+                    "kind": {
+                        "type": "STRING",
+                        "enum": ["PERSON", "ORG", "LOCATION", "DATE", "EVENT", "PRODUCT", "OTHER"]
+                    }
+                },
+                "required": ["text", "kind"]
+            }
+        });
+
+        self.send_prompt_json::<Vec<Entity>>(&prompt, Some(schema)).await
+    }
+
+    // Classify content into one of `categories` (DEFAULT_CATEGORIES when empty). An answer
+    // outside the list is retried once before failing with LlmParse.
+    pub async fn classify_content(
+        &self,
+        title: &str,
+        content: &str,
+        categories: &[&str],
+    ) -> Result<String, ScraperError> {
+        let categories = if categories.is_empty() { DEFAULT_CATEGORIES } else { categories };
         let prompt = format!(
             "Classify this web content into one main category. \
-            Choose from: Technology, News, Business, Education, Entertainment, \
-            Sports, Health, Science, Politics, Lifestyle, Other\n\n\
+            Choose from: {}\n\n\
             Title: {}\n\
             Content: {}\n\n\
             Return only the category name:",
+            categories.join(", "),
             title,
-            if content.len() > 2000 { &content[..2000] } else { content }
+            self.truncate_content(content)
         );
 
-        self.send_prompt(&prompt).await
+        // Deterministic output so the same page always lands in the same category
+        let config = GenerationConfig {
+            temperature: Some(0.0),
+            ..GenerationConfig::default()
+        };
+        let mut answer = self.send_prompt_with_config(&prompt, config.clone()).await?;
+        if let Some(category) = match_category(&answer, categories) {
+            return Ok(category.to_string());
+        }
+
+        let retry_prompt = format!(
+            "{}\n\n\"{}\" is not one of the allowed categories. \
+            Reply with exactly one of: {}",
+            prompt,
+            answer.trim(),
+            categories.join(", ")
+        );
+        answer = self.send_prompt_with_config(&retry_prompt, config).await?;
+        match match_category(&answer, categories) {
+            Some(category) => Ok(category.to_string()),
+            None => Err(ScraperError::LlmParse {
+                message: format!("Category is not one of: {}", categories.join(", ")),
+                raw: answer,
+            }),
+        }
     }
 
     // Check if content is relevant to specific keywords
-    pub async fn check_relevance(&self, content: &str, keywords: &[&str]) -> Result<f64, Box<dyn std::error::Error>> {
+    pub async fn check_relevance(&self, content: &str, keywords: &[&str]) -> Result<f64, ScraperError> {
         let keywords_str = keywords.join(", ");
         let prompt = format!(
             "Rate how relevant this content is to these keywords: {}\n\
@@ -287,27 +1670,284 @@ impl LLMRunner {
             100 = Highly relevant\n\n\
             Return only the number:",
             keywords_str,
-            if content.len() > 3000 { &content[..3000] } else { content }
+            self.truncate_content(content)
         );
 
         let response = self.send_prompt(&prompt).await?;
-        let score = response.trim().parse::<f64>().unwrap_or(0.0);
-        Ok(score.min(100.0).max(0.0))
+        let score = parse_relevance_score(&response).unwrap_or_else(|| {
+            warn!("No relevance score in LLM response, using 0: {}", response.trim());
+            0.0
+        });
+        Ok(score.clamp(0.0, 100.0))
+    }
+}
+
+// Analyses chosen with LLMRunner::analysis. Only the requested ones are sent to the LLM,
+// concurrently, and the first failure fails the run.
+#[must_use]
+pub struct AnalysisBuilder<'a> {
+    runner: &'a LLMRunner,
+    summary: bool,
+    sentiment: bool,
+    topics: Option<u32>,
+    category: bool,
+    relevance: Option<Vec<String>>,
+}
+
+// Results of AnalysisBuilder::run; analyses that weren't requested are None
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SelectedAnalysis {
+    pub url: String,
+    pub summary: Option<Summary>,
+    pub sentiment: Option<SentimentResult>,
+    pub topics: Option<Vec<String>>,
+    pub category: Option<String>,
+    pub relevance: Option<f64>,
+}
+
+impl AnalysisBuilder<'_> {
+    // A 3 sentence summary
+    pub fn summary(mut self) -> Self {
+        self.summary = true;
+        self
+    }
+
+    pub fn sentiment(mut self) -> Self {
+        self.sentiment = true;
+        self
+    }
+
+    // Up to `max_topics` key topics
+    pub fn topics(mut self, max_topics: u32) -> Self {
+        self.topics = Some(max_topics);
+        self
+    }
+
+    // One of DEFAULT_CATEGORIES
+    pub fn category(mut self) -> Self {
+        self.category = true;
+        self
+    }
+
+    // 0-100 relevance score for `keywords`
+    pub fn relevance(mut self, keywords: &[&str]) -> Self {
+        self.relevance = Some(keywords.iter().map(|keyword| keyword.to_string()).collect());
+        self
+    }
+
+    pub async fn run(self, title: &str, content: &str, url: &str) -> Result<SelectedAnalysis, ScraperError> {
+        let runner = self.runner;
+        let (summary, sentiment, topics, category, relevance) = tokio::join!(
+            async {
+                if self.summary {
+                    runner.summarize_content(content, 3).await.map(Some)
+                } else {
+                    Ok(None)
+                }
+            },
+            async {
+                if self.sentiment {
+                    runner.analyze_sentiment(runner.truncate_content(content)).await.map(Some)
+                } else {
+                    Ok(None)
+                }
+            },
+            async {
+                match self.topics {
+                    Some(max_topics) => runner.extract_topics(content, max_topics).await.map(Some),
+                    None => Ok(None),
+                }
+            },
+            async {
+                if self.category {
+                    runner.classify_content(title, content, &[]).await.map(Some)
+                } else {
+                    Ok(None)
+                }
+            },
+            async {
+                match &self.relevance {
+                    Some(keywords) => {
+                        let keywords: Vec<&str> = keywords.iter().map(String::as_str).collect();
+                        runner.check_relevance(content, &keywords).await.map(Some)
+                    }
+                    None => Ok(None),
+                }
+            },
+        );
+        Ok(SelectedAnalysis {
+            url: url.to_string(),
+            summary: summary?,
+            sentiment: sentiment?,
+            topics: topics?,
+            category: category?,
+            relevance: relevance?,
+        })
     }
 }
 
 // Helper functions
 impl LLMRunner {
     // Test connection to LLM
-    pub async fn test_connection(&self) -> Result<bool, Box<dyn std::error::Error>> {
+    pub async fn test_connection(&self) -> Result<bool, ScraperError> {
         let test_prompt = "Reply with 'OK' if you receive this message.";
         let response = self.send_prompt(test_prompt).await?;
         Ok(response.to_uppercase().contains("OK"))
     }
 
     // Get model info/status
-    pub async fn get_model_info(&self) -> Result<String, Box<dyn std::error::Error>> {
+    pub async fn get_model_info(&self) -> Result<String, ScraperError> {
         let prompt = "What model are you and what are your capabilities?";
         self.send_prompt(prompt).await
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runner_with(responses: &[&str]) -> LLMRunner {
+        LLMRunner::with_provider(MockProvider::new(responses.iter().copied()))
+    }
+
+    #[tokio::test]
+    async fn analyze_web_content_parses_sections() {
+        let runner = runner_with(&[
+            "SUMMARY: Rust 1.80 was released.\n\
+            SENTIMENT: POSITIVE - an upbeat announcement\n\
+            TOPICS: Rust, releases, compilers\n\
+            CATEGORY: Technology",
+        ]);
+        let analysis = runner.analyze_web_content("Rust 1.80", "Rust 1.80 is out.", "https://example.com").await.unwrap();
+        assert_eq!(analysis.summary, "Rust 1.80 was released.");
+        assert_eq!(analysis.sentiment, "POSITIVE - an upbeat announcement");
+        assert_eq!(analysis.key_topics, "Rust, releases, compilers");
+        assert_eq!(analysis.category, "Technology");
+    }
+
+    #[tokio::test]
+    async fn analyze_web_content_falls_back_to_the_raw_response() {
+        let runner = runner_with(&["Just some prose.", "Still just prose."]);
+        let analysis = runner.analyze_web_content("Title", "Content", "https://example.com").await.unwrap();
+        assert_eq!(analysis.summary, "Just some prose.");
+        assert_eq!(analysis.sentiment, "NEUTRAL");
+        assert_eq!(analysis.key_topics, "General");
+        assert_eq!(analysis.category, "General");
+    }
+
+    #[tokio::test]
+    async fn analyze_sentiment_reads_the_json_answer() {
+        let runner = runner_with(&[r#"{"label": "positive", "confidence": 0.9, "explanation": "Glowing review"}"#]);
+        let sentiment = runner.analyze_sentiment("I love it").await.unwrap();
+        assert_eq!(sentiment.label, "POSITIVE");
+        assert_eq!(sentiment.confidence, Some(90.0));
+        assert_eq!(sentiment.explanation, "Glowing review");
+    }
+
+    #[tokio::test]
+    async fn analyze_sentiment_falls_back_to_keywords() {
+        let runner = runner_with(&["This is quite negative.", "Overall negative, sorry."]);
+        let sentiment = runner.analyze_sentiment("I hate it").await.unwrap();
+        assert_eq!(sentiment.label, "NEGATIVE");
+        assert_eq!(sentiment.confidence, None);
+        assert_eq!(sentiment.explanation, "Overall negative, sorry.");
+    }
+
+    #[tokio::test]
+    async fn check_relevance_clamps_the_score() {
+        let runner = runner_with(&["250", "Score: 42", "no idea"]);
+        assert_eq!(runner.check_relevance("content", &["rust"]).await.unwrap(), 100.0);
+        assert_eq!(runner.check_relevance("content", &["rust"]).await.unwrap(), 42.0);
+        // No number at all scores 0
+        assert_eq!(runner.check_relevance("content", &["rust"]).await.unwrap(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn identical_prompts_are_answered_from_the_cache() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let provider = MockProvider::new(["First answer"]);
+        let prompts = provider.prompts();
+        let runner = LLMRunner::with_provider(provider)
+            .with_cache(cache_dir.path(), Duration::from_secs(60))
+            .unwrap();
+
+        assert_eq!(runner.send_prompt("Same prompt").await.unwrap(), "First answer");
+        assert_eq!(runner.send_prompt("Same prompt").await.unwrap(), "First answer");
+        assert_eq!(prompts.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn api_keys_fall_back_in_order() {
+        let lookup = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| vars.iter().find(|(var, _)| *var == name).map(|(_, value)| value.to_string())
+        };
+        let names = GeminiProvider::API_KEY_VARS;
+
+        let both = lookup(&[("GEMINI_API_KEY", "gemini-key"), ("LLM_API_KEY", "llm-key")]);
+        assert_eq!(first_key(&names, both).unwrap(), "gemini-key");
+
+        let blank_first = lookup(&[("GEMINI_API_KEY", "  "), ("LLM_API_KEY", "llm-key")]);
+        assert_eq!(first_key(&names, blank_first).unwrap(), "llm-key");
+
+        let fallback_only = lookup(&[("LLM_API_KEY", "llm-key")]);
+        assert_eq!(first_key(&OpenAiProvider::API_KEY_VARS, fallback_only).unwrap(), "llm-key");
+
+        match first_key(&names, lookup(&[("OPENAI_API_KEY", "openai-key")])) {
+            Err(e @ ScraperError::ApiKeyMissing { .. }) => {
+                let message = e.to_string();
+                assert!(message.contains("GEMINI_API_KEY, LLM_API_KEY"), "{}", message);
+            }
+            other => panic!("expected ApiKeyMissing, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn normalize_confidence_accepts_fractions_and_percentages() {
+        assert_eq!(normalize_confidence(0.85), 85.0);
+        assert_eq!(normalize_confidence(85.0), 85.0);
+        assert_eq!(normalize_confidence(1.0), 100.0);
+        assert_eq!(normalize_confidence(250.0), 100.0);
+        assert_eq!(normalize_confidence(-0.5), 0.0);
+        assert_eq!(normalize_confidence(f64::NAN), 0.0);
+    }
+
+    #[test]
+    fn parse_relevance_score_reads_common_answers() {
+        let cases = [
+            ("87", Some(87.0)),
+            ("Score: 87", Some(87.0)),
+            ("87%", Some(87.0)),
+            ("87/100", Some(87.0)),
+            ("4/5", Some(80.0)),
+            ("Relevance: 7.5 / 10", Some(75.0)),
+            ("Not relevant at all.", None),
+            ("", None),
+        ];
+        for (response, expected) in cases {
+            assert_eq!(parse_relevance_score(response), expected, "{:?}", response);
+        }
+    }
+
+    fn parse_sentiment(json: &str) -> Result<ParsedSentimentLLMResponse, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn sentiment_json_is_validated() {
+        let parsed = parse_sentiment(r#"{"label": "positive", "confidence": 80, "explanation": "Upbeat."}"#).unwrap();
+        assert_eq!((parsed.label.as_str(), parsed.confidence), ("POSITIVE", 80.0));
+
+        let missing = parse_sentiment(r#"{"label": "POSITIVE", "explanation": "Upbeat."}"#).unwrap_err();
+        assert!(missing.contains("missing field `confidence`"), "{}", missing);
+
+        let unknown =
+            parse_sentiment(r#"{"label": "POSITIVE", "confidence": 80, "explanation": "Upbeat.", "score": 1}"#).unwrap_err();
+        assert!(unknown.contains("unknown field `score`"), "{}", unknown);
+
+        let out_of_range =
+            parse_sentiment(r#"{"label": "POSITIVE", "confidence": 140, "explanation": "Upbeat."}"#).unwrap_err();
+        assert!(out_of_range.contains("out of range"), "{}", out_of_range);
+
+        let bad_label = parse_sentiment(r#"{"label": "HAPPY", "confidence": 80, "explanation": "Upbeat."}"#).unwrap_err();
+        assert!(bad_label.contains("HAPPY"), "{}", bad_label);
+    }
+}
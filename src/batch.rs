@@ -0,0 +1,387 @@
+// batch.rs - Runs the scrape + analysis pipeline over lists of URLs
+
+use std::collections::{BTreeMap, HashSet};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use futures::stream::{self, Stream, StreamExt};
+use tokio::sync::{Notify, Semaphore};
+use serde::Serialize;
+use tracing::instrument;
+use crate::error::ScraperError;
+use crate::pipeline::save_content_or_warn;
+use crate::scrape::{normalize_url, ScrapedPage, Scraper};
+use crate::text::{consolidate_topics, jaccard_similarity, shingles};
+use crate::LLM_run::{ContentAnalysis, LLMRunner};
+
+// Scraped page together with its LLM analysis
+#[derive(Debug, Clone)]
+pub struct PageAnalysis {
+    pub page: ScrapedPage,
+    pub analysis: ContentAnalysis,
+}
+
+// One URL per line; blank lines and `#` comments are ignored
+pub fn parse_url_list(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(normalize_url)
+        .collect()
+}
+
+// Keywords a page has to be about before it is analyzed, and the check_relevance score
+// (0-100) it needs to reach
+#[derive(Debug, Clone)]
+pub struct RelevanceFilter {
+    pub keywords: Vec<String>,
+    pub min_score: f64,
+}
+
+// Optional extra steps around the analysis of each page
+#[derive(Debug, Clone, Default)]
+pub struct AnalyzeOptions {
+    // Skip pages that aren't relevant enough to these keywords
+    pub relevance: Option<RelevanceFilter>,
+    // Re-classify each page into one of these categories with classify_content
+    pub categories: Vec<String>,
+    // In a batch, skip pages whose content is near-identical to a page already analyzed
+    pub dedup: bool,
+    // Lets the caller wind a batch down early, e.g. on Ctrl-C
+    pub stop: StopSignal,
+    // Directory to save the text sent to the LLM in, one file per URL
+    pub save_content: Option<PathBuf>,
+}
+
+// Shared flag for interrupting a batch, crawl or feed run. After stop() no new pages are
+// started; after abort() pages still in progress are abandoned too. Either way the pages
+// that didn't finish are reported as ScraperError::Cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct StopSignal(Arc<StopState>);
+
+#[derive(Debug, Default)]
+struct StopState {
+    stopped: AtomicBool,
+    aborted: AtomicBool,
+    abort: Notify,
+}
+
+impl StopSignal {
+    pub fn stop(&self) {
+        self.0.stopped.store(true, Ordering::SeqCst);
+    }
+
+    pub fn abort(&self) {
+        self.stop();
+        self.0.aborted.store(true, Ordering::SeqCst);
+        self.0.abort.notify_waiters();
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.0.stopped.load(Ordering::SeqCst)
+    }
+
+    // Resolves once abort() has been called
+    async fn aborted(&self) {
+        let notified = self.0.abort.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        if self.0.aborted.load(Ordering::SeqCst) {
+            return;
+        }
+        notified.await;
+    }
+
+    // Run `analysis` unless the run has been stopped, giving up on it if it is aborted
+    pub(crate) async fn run<F>(&self, analysis: F) -> Result<PageAnalysis, ScraperError>
+    where
+        F: Future<Output = Result<PageAnalysis, ScraperError>>,
+    {
+        if self.is_stopped() {
+            return Err(ScraperError::Cancelled);
+        }
+        tokio::select! {
+            result = analysis => result,
+            _ = self.aborted() => Err(ScraperError::Cancelled),
+        }
+    }
+}
+
+// Shingle similarity (0.0-1.0) at which two pages count as duplicates
+pub const DUPLICATE_SIMILARITY: f64 = 0.95;
+
+// Shingles of the pages seen so far in a batch, shared between workers
+#[derive(Debug, Default)]
+pub(crate) struct DuplicateIndex {
+    seen: Mutex<Vec<(String, HashSet<u64>)>>,
+}
+
+impl DuplicateIndex {
+    // The earlier page `page_shingles` duplicates and how similar the two are, if any
+    pub(crate) fn check(&self, page_shingles: &HashSet<u64>) -> Option<(String, f64)> {
+        let seen = self.seen.lock().expect("duplicate index lock poisoned");
+        seen.iter()
+            .map(|(original, other)| (original, jaccard_similarity(page_shingles, other)))
+            .find(|(_, similarity)| *similarity >= DUPLICATE_SIMILARITY)
+            .map(|(original, similarity)| (original.clone(), similarity))
+    }
+
+    // Remember an analyzed page, so later copies of it are reported by check
+    pub(crate) fn record(&self, url: &str, page_shingles: HashSet<u64>) {
+        self.seen.lock().expect("duplicate index lock poisoned").push((url.to_string(), page_shingles));
+    }
+}
+
+// Scrape a single URL and run analyze_web_content on the selected text. With a relevance
+// filter, pages scoring below its minimum fail with ScraperError::FilteredOut instead.
+pub async fn analyze_url(
+    runner: &LLMRunner,
+    scraper: &Scraper,
+    url: &str,
+    selector: &str,
+    options: &AnalyzeOptions,
+) -> Result<PageAnalysis, ScraperError> {
+    analyze_page(runner, scraper, url, selector, options, None).await
+}
+
+#[instrument(skip(runner, scraper, selector, options, duplicates))]
+async fn analyze_page(
+    runner: &LLMRunner,
+    scraper: &Scraper,
+    url: &str,
+    selector: &str,
+    options: &AnalyzeOptions,
+    duplicates: Option<&DuplicateIndex>,
+) -> Result<PageAnalysis, ScraperError> {
+    let page = scraper.scrape(url, selector).await?;
+    analyze_scraped(runner, page, options, duplicates).await
+}
+
+// Analysis steps of analyze_url for a page that has already been scraped
+pub(crate) async fn analyze_scraped(
+    runner: &LLMRunner,
+    page: ScrapedPage,
+    options: &AnalyzeOptions,
+    duplicates: Option<&DuplicateIndex>,
+) -> Result<PageAnalysis, ScraperError> {
+    // Pages are only recorded once analyzed, so a failed one doesn't mark its copies as duplicates
+    let page_shingles = duplicates.map(|_| shingles(&page.combined_content()));
+    if let Some(duplicates) = duplicates
+        && let Some(page_shingles) = &page_shingles
+        && let Some((original, similarity)) = duplicates.check(page_shingles)
+    {
+        return Err(ScraperError::Duplicate { original, similarity: similarity * 100.0 });
+    }
+    if let Some(dir) = &options.save_content {
+        save_content_or_warn(dir, &page.url, runner.truncate_content(&page.combined_content()));
+    }
+    if let Some(filter) = &options.relevance {
+        let keywords: Vec<&str> = filter.keywords.iter().map(String::as_str).collect();
+        let score = runner.check_relevance(&page.combined_content(), &keywords).await?;
+        if score < filter.min_score {
+            return Err(ScraperError::FilteredOut { score, min_score: filter.min_score });
+        }
+    }
+    let mut analysis = runner
+        .analyze_web_content_with_context(&page.title, &page.combined_content(), &page.url, page.analysis_context())
+        .await?;
+    if !options.categories.is_empty() {
+        let categories: Vec<&str> = options.categories.iter().map(String::as_str).collect();
+        analysis.category = runner.classify_content(&page.title, &page.combined_content(), &categories).await?;
+    }
+
+    if let Some(duplicates) = duplicates
+        && let Some(page_shingles) = page_shingles
+    {
+        duplicates.record(&page.url, page_shingles);
+    }
+    Ok(PageAnalysis { page, analysis })
+}
+
+// Most common topics listed in a BatchReport
+pub const REPORT_TOP_TOPICS: usize = 10;
+
+// Overview of a set of analyses: how many pages fell into each category and sentiment, and
+// the topics that came up most often
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchReport {
+    pub pages: usize,
+    pub categories: BTreeMap<String, usize>,
+    pub sentiments: BTreeMap<String, usize>,
+    // (topic, pages mentioning it), most common first
+    pub top_topics: Vec<(String, usize)>,
+}
+
+impl BatchReport {
+    pub fn from_analyses<'a>(analyses: impl IntoIterator<Item = &'a ContentAnalysis>) -> Self {
+        let mut report = BatchReport::default();
+        let mut page_topics = Vec::new();
+        for analysis in analyses {
+            report.pages += 1;
+            *report.categories.entry(analysis.category.trim().to_string()).or_default() += 1;
+            *report.sentiments.entry(sentiment_label(&analysis.sentiment).to_string()).or_default() += 1;
+            page_topics.push(analysis.key_topics.split(',').collect());
+        }
+
+        // Spelling variants of a topic are merged before ranking
+        report.top_topics = consolidate_topics(page_topics);
+        report.top_topics.truncate(REPORT_TOP_TOPICS);
+        report
+    }
+}
+
+// Leading label of a free-text sentiment such as "POSITIVE - upbeat review"
+fn sentiment_label(sentiment: &str) -> &'static str {
+    let upper = sentiment.to_uppercase();
+    ["POSITIVE", "NEGATIVE", "NEUTRAL", "MIXED"]
+        .into_iter()
+        .filter_map(|label| upper.find(label).map(|index| (index, label)))
+        .min()
+        .map_or("UNKNOWN", |(_, label)| label)
+}
+
+// Progress notifications from analyze_urls_with_progress
+#[derive(Debug, Clone, Copy)]
+pub enum BatchEvent<'a> {
+    Started { url: &'a str },
+    Finished { url: &'a str, succeeded: bool },
+}
+
+// Analyze `urls` with at most `concurrency` pages in flight, returning results in input order.
+// The runner is shared, so its rate limit applies across all workers.
+pub async fn analyze_urls(
+    runner: Arc<LLMRunner>,
+    scraper: Arc<Scraper>,
+    urls: Vec<String>,
+    selector: &str,
+    concurrency: usize,
+) -> Vec<(String, Result<PageAnalysis, ScraperError>)> {
+    analyze_urls_with_progress(runner, scraper, urls, selector, concurrency, AnalyzeOptions::default(), |_| {}).await
+}
+
+// Same as analyze_urls, applying `options` to every page and calling `on_event` as each
+// URL starts and finishes. With `options.dedup`, pages near-identical to one analyzed
+// earlier in the batch fail with ScraperError::Duplicate before reaching the LLM.
+pub async fn analyze_urls_with_progress(
+    runner: Arc<LLMRunner>,
+    scraper: Arc<Scraper>,
+    urls: Vec<String>,
+    selector: &str,
+    concurrency: usize,
+    options: AnalyzeOptions,
+    on_event: impl Fn(BatchEvent<'_>) + Send + Sync + 'static,
+) -> Vec<(String, Result<PageAnalysis, ScraperError>)> {
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let on_event = Arc::new(on_event);
+    let duplicates = options.dedup.then(|| Arc::new(DuplicateIndex::default()));
+    let options = Arc::new(options);
+
+    let handles: Vec<_> = urls
+        .into_iter()
+        .map(|url| {
+            let runner = Arc::clone(&runner);
+            let scraper = Arc::clone(&scraper);
+            let semaphore = Arc::clone(&semaphore);
+            let selector = selector.to_string();
+            let on_event = Arc::clone(&on_event);
+            let options = Arc::clone(&options);
+            let duplicates = duplicates.clone();
+            tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await.expect("batch semaphore closed");
+                on_event(BatchEvent::Started { url: &url });
+                let result = options
+                    .stop
+                    .run(analyze_page(&runner, &scraper, &url, &selector, &options, duplicates.as_deref()))
+                    .await;
+                // Pages skipped as irrelevant, duplicate or by an interrupt aren't failures
+                let succeeded = matches!(
+                    result,
+                    Ok(_)
+                        | Err(ScraperError::FilteredOut { .. }
+                            | ScraperError::Duplicate { .. }
+                            | ScraperError::Cancelled)
+                );
+                on_event(BatchEvent::Finished { url: &url, succeeded });
+                (url, result)
+            })
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        results.push(handle.await.expect("batch analysis task panicked"));
+    }
+    results
+}
+
+// Like analyze_urls_with_progress, but yields each result as soon as its page is done
+// (not in input order) and only keeps `concurrency` pages in memory at a time
+pub fn analyze_urls_stream<'a>(
+    runner: &'a LLMRunner,
+    scraper: &'a Scraper,
+    urls: Vec<String>,
+    selector: &'a str,
+    concurrency: usize,
+    options: &'a AnalyzeOptions,
+) -> impl Stream<Item = (String, Result<PageAnalysis, ScraperError>)> + 'a {
+    let duplicates = options.dedup.then(|| Arc::new(DuplicateIndex::default()));
+    stream::iter(urls)
+        .map(move |url| {
+            let duplicates = duplicates.clone();
+            async move {
+                let result = options
+                    .stop
+                    .run(analyze_page(runner, scraper, &url, selector, options, duplicates.as_deref()))
+                    .await;
+                (url, result)
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrape::PageMetadata;
+    use crate::LLM_run::MockProvider;
+
+    const ANALYSIS: &str = "SUMMARY: A page.\nSENTIMENT: NEUTRAL\nTOPICS: testing\nCATEGORY: Technology";
+
+    fn page(url: &str) -> ScrapedPage {
+        ScrapedPage {
+            url: url.to_string(),
+            title: "Title".to_string(),
+            content_parts: vec!["the same article text served under two different addresses".to_string()],
+            unmatched_selectors: Vec::new(),
+            auto_extracted: false,
+            broadened_selector: None,
+            metadata: PageMetadata::default(),
+            separator: "\n".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_pages_are_not_recorded_as_originals() {
+        let options = AnalyzeOptions { dedup: true, ..AnalyzeOptions::default() };
+        let duplicates = DuplicateIndex::default();
+
+        // No responses: the analysis fails with EmptyResponse
+        let failing = LLMRunner::with_provider(MockProvider::new(Vec::<String>::new()));
+        let first = analyze_scraped(&failing, page("https://a.example"), &options, Some(&duplicates)).await;
+        assert!(matches!(first, Err(ScraperError::EmptyResponse)));
+
+        let provider = MockProvider::new(Vec::<String>::new()).with_fallback(ANALYSIS);
+        let prompts = provider.prompts();
+        let runner = LLMRunner::with_provider(provider);
+        let second = analyze_scraped(&runner, page("https://b.example"), &options, Some(&duplicates)).await.unwrap();
+        // Parsed on the first answer, without a format-reminder retry
+        assert_eq!(second.analysis.key_topics, "testing");
+        assert_eq!(prompts.lock().unwrap().len(), 1);
+
+        match analyze_scraped(&runner, page("https://c.example"), &options, Some(&duplicates)).await {
+            Err(ScraperError::Duplicate { original, .. }) => assert_eq!(original, "https://b.example"),
+            other => panic!("expected Duplicate, got {:?}", other.err()),
+        }
+    }
+}
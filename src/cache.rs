@@ -0,0 +1,79 @@
+// cache.rs - Simple on-disk key/value cache with expiry, used for LLM responses and fetched pages
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// How long entries stay fresh unless configured otherwise
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+// What is stored per key
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CacheEntry {
+    // Seconds since the Unix epoch when the entry was written
+    pub stored_at: u64,
+    pub value: String,
+}
+
+pub struct DiskCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl DiskCache {
+    // Open (creating if needed) a cache directory whose entries expire after `ttl`
+    pub fn new(dir: impl AsRef<Path>, ttl: Duration) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, ttl })
+    }
+
+    // SHA-256 hex digest of the key material
+    pub fn key(parts: &[&str]) -> String {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update(part.as_bytes());
+            hasher.update([0u8]);
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    // Cached entry for `key` if present and younger than the TTL
+    pub fn get_entry(&self, key: &str) -> Option<CacheEntry> {
+        let data = fs::read_to_string(self.path(key)).ok()?;
+        let entry: CacheEntry = serde_json::from_str(&data).ok()?;
+        let age = now_secs().saturating_sub(entry.stored_at);
+        (age <= self.ttl.as_secs()).then_some(entry)
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.get_entry(key).map(|entry| entry.value)
+    }
+
+    pub fn put(&self, key: &str, value: &str) -> io::Result<()> {
+        let entry = CacheEntry {
+            stored_at: now_secs(),
+            value: value.to_string(),
+        };
+        fs::write(self.path(key), serde_json::to_string(&entry)?)
+    }
+}
+
+// Seconds since the Unix epoch
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,107 @@
+// clean.rs - Tidy scraped text before it is sent to the LLM
+
+use std::fs;
+use std::path::Path;
+use regex::{Regex, RegexBuilder};
+use crate::error::ScraperError;
+
+// Lines dropped by default: share buttons, cookie notices, newsletter prompts and other
+// page furniture that survives extraction
+pub const DEFAULT_BOILERPLATE_PATTERNS: &[&str] = &[
+    r"^(share|tweet|pin it|email|print)( (this|on|via|to)\b.*)?$",
+    r"^(follow|like) us\b",
+    r"\b(we|this (site|website)) uses? cookies\b",
+    r"^(accept|reject|allow|manage) (all )?cookies$",
+    r"^cookie (policy|settings|preferences)$",
+    r"^(subscribe|sign up)\b.*\bnewsletter\b",
+    r"^(advertisement|sponsored( content)?)$",
+    r"^(read more|continue reading|related (articles|posts|stories)|you (may|might) also like)\b",
+    r"^skip to (main )?content$",
+    r"all rights reserved",
+];
+
+// Only lines up to this long are checked against the boilerplate patterns, so a paragraph
+// that happens to mention cookies is kept
+const MAX_BOILERPLATE_LINE_CHARS: usize = 200;
+
+// Zero-width and invisible formatting characters that only waste tokens
+const INVISIBLE_CHARS: &[char] = &['\u{00AD}', '\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}'];
+
+// Collapses whitespace, strips control and zero-width characters and drops boilerplate
+// lines. Patterns are matched case-insensitively against each trimmed line.
+#[derive(Debug, Clone)]
+pub struct ContentCleaner {
+    boilerplate: Vec<Regex>,
+}
+
+impl Default for ContentCleaner {
+    fn default() -> Self {
+        Self::new(DEFAULT_BOILERPLATE_PATTERNS).expect("default boilerplate patterns are valid")
+    }
+}
+
+impl ContentCleaner {
+    pub fn new(patterns: &[&str]) -> Result<Self, ScraperError> {
+        let mut cleaner = Self { boilerplate: Vec::new() };
+        cleaner.add_patterns(patterns)?;
+        Ok(cleaner)
+    }
+
+    // Also drop lines matching `patterns`
+    pub fn add_patterns(&mut self, patterns: &[&str]) -> Result<(), ScraperError> {
+        for pattern in patterns {
+            let regex = RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| ScraperError::Config(format!("Invalid boilerplate pattern '{}': {}", pattern, e)))?;
+            self.boilerplate.push(regex);
+        }
+        Ok(())
+    }
+
+    // Add the patterns in a file, one regex per line (blank lines and # comments are skipped)
+    pub fn add_patterns_file(&mut self, path: impl AsRef<Path>) -> Result<(), ScraperError> {
+        let contents = fs::read_to_string(path)?;
+        let patterns: Vec<&str> = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+        self.add_patterns(&patterns)
+    }
+
+    pub fn clean(&self, text: &str) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        for line in text.lines() {
+            let line: String = line
+                .chars()
+                .filter(|c| !INVISIBLE_CHARS.contains(c))
+                .map(|c| if c.is_control() { ' ' } else { c })
+                .collect();
+            let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+            if self.is_boilerplate(&line) {
+                continue;
+            }
+            // Keep paragraph breaks, but never more than one blank line in a row
+            if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+                continue;
+            }
+            lines.push(line);
+        }
+        while lines.last().is_some_and(|last| last.is_empty()) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+
+    fn is_boilerplate(&self, line: &str) -> bool {
+        !line.is_empty()
+            && line.chars().count() <= MAX_BOILERPLATE_LINE_CHARS
+            && self.boilerplate.iter().any(|regex| regex.is_match(line))
+    }
+}
+
+// Clean `text` with the default boilerplate patterns
+pub fn clean_content(text: &str) -> String {
+    ContentCleaner::default().clean(text)
+}
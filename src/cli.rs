@@ -0,0 +1,348 @@
+// cli.rs - Command-line arguments for the scraper binary
+
+use std::path::PathBuf;
+use clap::{ArgAction, Parser, ValueEnum};
+use web_scrapper_project::scrape::{
+    DEFAULT_BROADEN_BELOW_CHARS, DEFAULT_MAX_REDIRECTS, DEFAULT_MAX_RESPONSE_BYTES, DEFAULT_MIN_CONTENT_CHARS,
+};
+use web_scrapper_project::LLM_run::DEFAULT_MAX_CONTENT_CHARS;
+
+// How results are written to stdout
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    // One JSON object per line, written as each result is ready
+    Jsonl,
+    Csv,
+}
+
+// How each page's analysis is produced
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Mode {
+    // One prompt asking for every section
+    Single,
+    // Separate summary, sentiment, topics and category calls
+    Composed,
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "scraper", version, about = "Scrape a web page and analyze its content with an LLM")]
+pub struct Args {
+    /// Page to scrape: a URL, a file:// URL or a path to a local HTML file (prompted for when omitted)
+    pub url: Option<String>,
+
+    /// CSS selector(s) for the main content, separated by ',' or ';' (prompted for when omitted;
+    /// leave blank to detect the main content automatically)
+    pub selector: Option<String>,
+
+    /// CSS selector for the main content; in batch mode pages are auto-extracted without one
+    #[arg(long = "selector", value_name = "SELECTOR")]
+    pub selector_flag: Option<String>,
+
+    /// Treat the content selector(s) as XPath 1.0 expressions instead of CSS selectors
+    #[arg(long, conflicts_with = "selector_test")]
+    pub xpath: bool,
+
+    /// Skip pages with fewer non-whitespace characters of content than this
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MIN_CONTENT_CHARS)]
+    pub min_content_chars: usize,
+
+    /// When the selector matches fewer non-whitespace characters than this, use the first of
+    /// article, main or body with enough text instead (0 to disable)
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_BROADEN_BELOW_CHARS)]
+    pub broaden_below: usize,
+
+    /// Only analyze the section starting at the heading with this text, up to the next heading
+    /// of the same or a higher level (the whole page is used when no heading matches)
+    #[arg(long, value_name = "TEXT")]
+    pub from_heading: Option<String>,
+
+    /// Analyze the part of the page matching SELECTOR on its own, labeled NAME, e.g.
+    /// --region reviews=.reviews (repeatable; replaces the main content selector)
+    #[arg(long = "region", value_name = "NAME=SELECTOR", conflicts_with_all = [
+        "urls_file", "sitemap", "feed", "crawl", "links", "stdin_urls", "dry_run", "extractive", "selector_test",
+        "repl", "check",
+    ])]
+    pub regions: Vec<String>,
+
+    /// Drop text inside elements matching this selector within the matched content (repeatable)
+    #[arg(long = "exclude", value_name = "SELECTOR")]
+    pub excludes: Vec<String>,
+
+    /// Also capture the parent's own text and the neighbouring sibling elements of each match,
+    /// for pages whose prose is split across elements (can add noise)
+    #[arg(long)]
+    pub with_context: bool,
+
+    /// Text placed between matched content blocks; \n and \t are turned into a newline and a
+    /// tab (defaults to a blank line)
+    #[arg(long, value_name = "TEXT")]
+    pub separator: Option<String>,
+
+    /// Keep paragraphs, headings and list items of each match on their own lines and skip
+    /// matches nested in an earlier match, so separators only fall between distinct matches
+    #[arg(long)]
+    pub preserve_blocks: bool,
+
+    /// Collapse whitespace, strip control and zero-width characters, and drop boilerplate
+    /// lines such as share buttons and cookie notices before analysis
+    #[arg(long)]
+    pub clean: bool,
+
+    /// With --clean, file of extra boilerplate regexes, one per line, matched
+    /// case-insensitively against each line of content
+    #[arg(long, value_name = "PATH", requires = "clean")]
+    pub boilerplate: Option<PathBuf>,
+
+    /// Send matched content to the LLM as Markdown, keeping headings, lists, links and code blocks
+    #[arg(long)]
+    pub markdown: bool,
+
+    /// Scrape and print the text that would be sent to the LLM, without calling it
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Print the sentences that best match --keywords instead of calling the LLM
+    #[arg(long)]
+    pub extractive: bool,
+
+    /// With --extractive, how many sentences to keep
+    #[arg(long, value_name = "N", default_value_t = 3, requires = "extractive")]
+    pub sentences: usize,
+
+    /// Translate the scraped content into this language (e.g. English or en) before analysis
+    #[arg(long, value_name = "LANG")]
+    pub translate: Option<String>,
+
+    /// Also analyze the sentiment of the first 500 characters in a separate LLM call (the
+    /// analysis already reports the overall sentiment)
+    #[arg(long)]
+    pub snippet_sentiment: bool,
+
+    /// Ask a question about the scraped page; the answer is grounded in its content
+    #[arg(long, value_name = "QUESTION")]
+    pub ask: Option<String>,
+
+    /// Fetch the page once and show how many elements each of the given selectors matches,
+    /// with a preview of the first match, without calling the LLM
+    #[arg(long, conflicts_with_all = ["urls_file", "sitemap", "feed", "crawl", "links", "stdin_urls", "check"])]
+    pub selector_test: bool,
+
+    /// Scrape the page once, then read commands such as `summary`, `topics 5`, `ask QUESTION`
+    /// or `relevance a,b,c` from stdin and answer each from the same content until `quit`
+    #[arg(long, conflicts_with_all = [
+        "urls_file", "sitemap", "feed", "crawl", "links", "stdin_html", "stdin_urls", "dry_run", "extractive",
+        "selector_test", "check",
+    ])]
+    pub repl: bool,
+
+    /// Save the text sent to the LLM for each page (after cleaning, translation and
+    /// truncation) in DIR as <url-slug>.txt, to compare runs
+    #[arg(long, value_name = "DIR")]
+    pub save_content: Option<PathBuf>,
+
+    /// Print LLM request and token usage on stderr when the run finishes
+    #[arg(long)]
+    pub usage: bool,
+
+    /// Check that the LLM backend answers with the configured key and model, print what the
+    /// model says about itself, and exit (non-zero on failure)
+    #[arg(long, conflicts_with_all = ["urls_file", "sitemap", "feed", "crawl", "stdin_html", "stdin_urls"])]
+    pub check: bool,
+
+    /// Print the page's links instead of running LLM analysis
+    #[arg(long)]
+    pub links: bool,
+
+    /// With --links, only list links on the same domain as the page
+    #[arg(long, requires = "links")]
+    pub same_domain: bool,
+
+    /// File with one URL per line to analyze in batch (blank lines and # comments are skipped)
+    #[arg(long, value_name = "PATH")]
+    pub urls_file: Option<PathBuf>,
+
+    /// Parse the HTML document piped on stdin instead of fetching URL (which, if given, is
+    /// only used to label the output)
+    #[arg(long, conflicts_with_all = ["urls_file", "sitemap", "feed", "crawl", "links", "stdin_urls"])]
+    pub stdin_html: bool,
+
+    /// Analyze the URLs piped on stdin, one per line, in batch mode
+    #[arg(long, conflicts_with_all = ["urls_file", "sitemap", "feed", "crawl"])]
+    pub stdin_urls: bool,
+
+    /// Sitemap (sitemap.xml, sitemap index or .xml.gz) whose URLs are analyzed in batch
+    #[arg(long, value_name = "URL", conflicts_with = "urls_file")]
+    pub sitemap: Option<String>,
+
+    /// RSS or Atom feed whose entries are analyzed one by one
+    #[arg(long, value_name = "URL", conflicts_with_all = ["urls_file", "sitemap", "links", "dry_run"])]
+    pub feed: Option<String>,
+
+    /// With --feed, scrape each entry's link instead of analyzing the content inlined in the feed
+    #[arg(long, requires = "feed")]
+    pub fetch_links: bool,
+
+    /// Crawl the site from URL, following same-domain links breadth-first, and analyze every page
+    #[arg(long, conflicts_with_all = ["urls_file", "sitemap", "feed", "links", "dry_run"])]
+    pub crawl: bool,
+
+    /// With --crawl, how many links deep to follow from the starting page
+    #[arg(long, value_name = "N", default_value_t = 2, requires = "crawl")]
+    pub max_depth: usize,
+
+    /// With --crawl, the most pages to fetch
+    #[arg(long, value_name = "N", default_value_t = 20, requires = "crawl")]
+    pub max_pages: usize,
+
+    /// Maximum number of URLs analyzed at the same time in batch, crawl and feed mode
+    #[arg(long, value_name = "N", default_value_t = 4)]
+    pub concurrency: usize,
+
+    /// After a batch, crawl or feed run, summarize categories, sentiments and common topics
+    #[arg(long)]
+    pub report: bool,
+
+    /// In batch mode, skip pages whose text is at least 95% similar to a page already analyzed
+    #[arg(long)]
+    pub dedup: bool,
+
+    /// Categories to classify pages into, separated by commas (replaces the built-in list)
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    pub categories: Vec<String>,
+
+    /// Keywords for --min-relevance and --extractive, separated by commas
+    #[arg(long, value_name = "WORDS", value_delimiter = ',')]
+    pub keywords: Vec<String>,
+
+    /// In batch mode, skip pages whose relevance to --keywords scores below this (0-100)
+    #[arg(long, value_name = "SCORE", requires = "keywords")]
+    pub min_relevance: Option<f64>,
+
+    /// Instruction applied to every LLM prompt, e.g. "Answer as a financial analyst."
+    #[arg(long, value_name = "TEXT")]
+    pub system_instruction: Option<String>,
+
+    /// File with a custom analysis prompt using {url}, {title}, {context} and {content}
+    /// placeholders; `LABEL: ...` lines declare the sections to parse from the answer
+    #[arg(long, value_name = "PATH")]
+    pub prompt_template: Option<PathBuf>,
+
+    /// SQLite database to store analysis results in; URLs already stored are skipped
+    #[arg(long, value_name = "PATH")]
+    pub db: Option<PathBuf>,
+
+    /// Analyze with one combined prompt, or with separate, more reliable calls per section
+    #[arg(long, value_enum, default_value_t = Mode::Single)]
+    pub mode: Mode,
+
+    /// Include the raw LLM response with each analysis, to debug prompts and parsing
+    #[arg(long)]
+    pub include_raw: bool,
+
+    /// Most characters of page content sent with each LLM prompt; longer content is cut
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_CONTENT_CHARS)]
+    pub max_content_chars: usize,
+
+    /// LLM model to use (e.g. gemini-1.5-flash); defaults to the provider's default model
+    #[arg(long, value_name = "NAME")]
+    pub model: Option<String>,
+
+    /// Directory for cached fetched pages (html/) and LLM responses (llm/), reused for 24 hours.
+    /// Page contents and model answers are stored there as plain JSON files.
+    #[arg(long, value_name = "DIR", default_value = ".cache")]
+    pub cache_dir: PathBuf,
+
+    /// Always fetch pages and call the LLM instead of reusing results cached in --cache-dir
+    /// (--refresh re-downloads pages only)
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// User-Agent header sent when fetching pages (defaults to a browser-like string)
+    #[arg(long, value_name = "STRING")]
+    pub user_agent: Option<String>,
+
+    /// Extra request header as "Name: value", e.g. "Authorization: Bearer ..." (repeatable;
+    /// values are never logged)
+    #[arg(long = "header", value_name = "HEADER")]
+    pub headers: Vec<String>,
+
+    /// Cookie header sent with every page request, e.g. "session=abc123", to scrape pages
+    /// behind a login (the value is never logged)
+    #[arg(long, value_name = "COOKIES")]
+    pub cookie: Option<String>,
+
+    /// Proxy for fetching pages, e.g. http://proxy.corp:3128 (defaults to HTTP_PROXY/HTTPS_PROXY)
+    #[arg(long, value_name = "URL")]
+    pub proxy: Option<String>,
+
+    /// Maximum number of redirects to follow when fetching a page
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_REDIRECTS)]
+    pub max_redirects: usize,
+
+    /// Largest page (after decompression) to download, in bytes; bigger responses are abandoned
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_MAX_RESPONSE_BYTES)]
+    pub max_response_bytes: usize,
+
+    /// Minimum seconds between requests to the same host
+    #[arg(long, value_name = "SECONDS", default_value_t = 0.0)]
+    pub delay: f64,
+
+    /// Skip pages that the site's robots.txt disallows for this scraper
+    #[arg(long)]
+    pub respect_robots: bool,
+
+    /// Re-download pages even when a cached copy is still fresh, and re-analyze URLs already
+    /// stored in --db
+    #[arg(long)]
+    pub refresh: bool,
+
+    /// Show more diagnostic output on stderr (-v for debug, -vv for trace); RUST_LOG overrides
+    #[arg(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Only show warnings and errors on stderr
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Write results to this file instead of stdout (progress and logs stay on stderr)
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Output format for the analysis results; json wraps them in a document with a
+    /// schema_version that changes whenever a field is removed, renamed or changes meaning
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+impl Args {
+    // Selector from --selector, falling back to the positional argument
+    pub fn selector(&self) -> Option<&str> {
+        self.selector_flag
+            .as_deref()
+            .or(self.selector.as_deref())
+            .map(str::trim)
+    }
+
+    // --separator with its \n and \t escapes expanded
+    pub fn separator(&self) -> Option<String> {
+        self.separator
+            .as_deref()
+            .map(|separator| separator.replace("\\n", "\n").replace("\\t", "\t"))
+    }
+
+    // --categories entries, trimmed, without empty ones
+    pub fn categories(&self) -> Vec<&str> {
+        trimmed_list(&self.categories)
+    }
+
+    // --keywords entries, trimmed, without empty ones
+    pub fn keywords(&self) -> Vec<&str> {
+        trimmed_list(&self.keywords)
+    }
+}
+
+fn trimmed_list(values: &[String]) -> Vec<&str> {
+    values.iter().map(|value| value.trim()).filter(|value| !value.is_empty()).collect()
+}
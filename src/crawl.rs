@@ -0,0 +1,126 @@
+// crawl.rs - Breadth-first crawl of a small site, analyzing every page it reaches
+
+use std::collections::{BTreeMap, HashSet};
+use futures::stream::{self, StreamExt};
+use tracing::{debug, info, instrument};
+use crate::batch::{analyze_scraped, AnalyzeOptions, DuplicateIndex, PageAnalysis};
+use crate::error::ScraperError;
+use crate::scrape::{filter_same_domain, Scraper};
+use crate::LLM_run::LLMRunner;
+
+// Limits on how far a crawl goes
+#[derive(Debug, Clone)]
+pub struct CrawlOptions {
+    // Link hops followed from the seed page; 0 only analyzes the seed
+    pub max_depth: usize,
+    // Most pages fetched in total, the seed included
+    pub max_pages: usize,
+    // Pages fetched and analyzed at the same time within one depth level
+    pub concurrency: usize,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self { max_depth: 2, max_pages: 20, concurrency: 4 }
+    }
+}
+
+// Result of visiting one page: where it ended up, the links on it and its analysis
+struct CrawledPage {
+    final_url: Option<String>,
+    links: Vec<String>,
+    result: Result<PageAnalysis, ScraperError>,
+}
+
+// Analyze `seed` and the pages on its domain reachable through links, breadth-first, within
+// `limits`. Every URL is visited once; links to non-HTML resources are left out of the
+// results. Robots.txt and the crawl delay apply as configured on the scraper.
+#[instrument(skip(runner, scraper, selector, limits, options))]
+pub async fn crawl(
+    runner: &LLMRunner,
+    scraper: &Scraper,
+    seed: &str,
+    selector: &str,
+    limits: &CrawlOptions,
+    options: &AnalyzeOptions,
+) -> BTreeMap<String, Result<PageAnalysis, ScraperError>> {
+    let duplicates = options.dedup.then(DuplicateIndex::default);
+    let max_pages = limits.max_pages.max(1);
+    let mut visited = HashSet::from([seed.to_string()]);
+    let mut results = BTreeMap::new();
+    // Links have to share a domain with the seed's final URL
+    let mut site = seed.to_string();
+    let mut level = vec![seed.to_string()];
+
+    for depth in 0..=limits.max_depth {
+        if level.is_empty() || options.stop.is_stopped() {
+            break;
+        }
+        info!("Crawling {} pages at depth {}", level.len(), depth);
+        let pages: Vec<(String, CrawledPage)> = stream::iter(level)
+            .map(|url| {
+                let duplicates = duplicates.as_ref();
+                async move {
+                    let page = crawl_page(runner, scraper, &url, selector, options, duplicates).await;
+                    (url, page)
+                }
+            })
+            .buffered(limits.concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut next = Vec::new();
+        for (url, page) in pages {
+            if let Some(final_url) = &page.final_url {
+                if depth == 0 {
+                    site = final_url.clone();
+                }
+                visited.insert(final_url.clone());
+            }
+            if depth < limits.max_depth {
+                for link in filter_same_domain(&page.links, &site) {
+                    if visited.len() >= max_pages {
+                        break;
+                    }
+                    if visited.insert(link.clone()) {
+                        next.push(link);
+                    }
+                }
+            }
+            match page.result {
+                Err(ScraperError::UnsupportedContentType { content_type, .. }) => {
+                    debug!("Not analyzing {} ({})", url, content_type);
+                }
+                result => {
+                    results.insert(url, result);
+                }
+            }
+        }
+        level = next;
+    }
+    results
+}
+
+async fn crawl_page(
+    runner: &LLMRunner,
+    scraper: &Scraper,
+    url: &str,
+    selector: &str,
+    options: &AnalyzeOptions,
+    duplicates: Option<&DuplicateIndex>,
+) -> CrawledPage {
+    if options.stop.is_stopped() {
+        return CrawledPage { final_url: None, links: Vec::new(), result: Err(ScraperError::Cancelled) };
+    }
+    let fetched = match scraper.fetch(url).await {
+        Ok(fetched) => fetched,
+        Err(e) => return CrawledPage { final_url: None, links: Vec::new(), result: Err(e) },
+    };
+    let final_url = fetched.final_url.clone();
+    let links = Scraper::extract_links(&fetched.html, &final_url);
+    let result = match scraper.scrape_fetched(fetched, selector) {
+        Ok(page) => options.stop.run(analyze_scraped(runner, page, options, duplicates)).await,
+        Err(e) => Err(e),
+    };
+    CrawledPage { final_url: Some(final_url), links, result }
+}
@@ -0,0 +1,131 @@
+// error.rs - Error type shared by the scraper and the LLM runner
+
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ScraperError {
+    #[error("Failed to fetch {url}: {source}")]
+    Fetch {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    #[error("Failed to read {path}: {source}")]
+    ReadFile {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Fetching {url} failed with HTTP status {status}")]
+    HttpStatus { url: String, status: u16 },
+
+    #[error("Response from {url} is larger than the {limit} byte limit")]
+    ResponseTooLarge { url: String, limit: usize },
+
+    #[error("{url} is not an HTML page or PDF (Content-Type: {content_type})")]
+    UnsupportedContentType { url: String, content_type: String },
+
+    #[error("Failed to extract text from PDF {url}: {message}")]
+    PdfExtract { url: String, message: String },
+
+    #[error("Failed to parse feed {url}: {message}")]
+    FeedParse { url: String, message: String },
+
+    #[error("Redirect error while fetching {url}: {source}")]
+    Redirect {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    #[error("Could not connect through proxy {proxy} while fetching {url}: {source}")]
+    Proxy {
+        proxy: String,
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    #[error("Fetching {url} is disallowed by the site's robots.txt")]
+    RobotsDisallowed { url: String },
+
+    #[error("Failed to parse content selector '{selector}': {message}")]
+    SelectorParse { selector: String, message: String },
+
+    #[error("No content found matching selector: '{selector}'")]
+    EmptyContent { selector: String },
+
+    #[error("Not enough content to analyze on {url}: {chars} non-whitespace characters, at least {min_chars} needed")]
+    InsufficientContent { url: String, chars: usize, min_chars: usize },
+
+    #[error("Filtered out: relevance score {score:.0} is below the minimum of {min_score:.0}")]
+    FilteredOut { score: f64, min_score: f64 },
+
+    #[error("Duplicate: content is {similarity:.0}% similar to {original}")]
+    Duplicate { original: String, similarity: f64 },
+
+    #[error("Cancelled before it finished")]
+    Cancelled,
+
+    #[error("No API key found: set one of {vars} in your .env file or environment")]
+    ApiKeyMissing { vars: String },
+
+    #[error("API request failed ({status}): {body}")]
+    ApiRequest {
+        status: u16,
+        body: String,
+        // Wait the server asked for in a Retry-After header
+        retry_after: Option<Duration>,
+    },
+
+    #[error("LLM request timed out")]
+    Timeout,
+
+    #[error("No response from LLM")]
+    EmptyResponse,
+
+    #[error("LLM blocked the prompt: {reason}")]
+    Blocked { reason: String },
+
+    #[error("Failed to parse LLM response: {message}")]
+    LlmParse { message: String, raw: String },
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+}
+
+impl ScraperError {
+    // Whether a failed request is worth retrying (throttling, server errors, connection problems)
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ScraperError::ApiRequest { status, .. } | ScraperError::HttpStatus { status, .. } => {
+                matches!(status, 429 | 500 | 502 | 503)
+            }
+            ScraperError::Timeout => true,
+            ScraperError::Http(e) | ScraperError::Fetch { source: e, .. } | ScraperError::Proxy { source: e, .. } => {
+                e.is_connect() || e.is_timeout()
+            }
+            _ => false,
+        }
+    }
+
+    // How long the server asked the client to wait before retrying, if it said
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ScraperError::ApiRequest { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// feed.rs - RSS/Atom feeds: parses entries and analyzes each one as a page
+
+use futures::stream::{self, StreamExt};
+use crate::batch::{analyze_scraped, AnalyzeOptions, DuplicateIndex, PageAnalysis};
+use crate::error::ScraperError;
+use crate::scrape::{PageMetadata, ScrapedPage, Scraper};
+use crate::LLM_run::LLMRunner;
+
+// One item of a feed
+#[derive(Debug, Clone)]
+pub struct FeedEntry {
+    pub title: String,
+    // Link to the full article, resolved against the feed URL
+    pub link: Option<String>,
+    // Inline content, or the summary when there is none; usually HTML
+    pub content: Option<String>,
+}
+
+impl FeedEntry {
+    // Key the entry's results are reported under: its link, or its title
+    pub fn key(&self) -> &str {
+        self.link.as_deref().unwrap_or(&self.title)
+    }
+}
+
+// Entries of an RSS, Atom or JSON feed downloaded from `url`
+pub fn parse_feed(bytes: &[u8], url: &str) -> Result<Vec<FeedEntry>, ScraperError> {
+    let feed = feed_rs::parser::Builder::new()
+        .base_uri(Some(url))
+        .build()
+        .parse(bytes)
+        .map_err(|e| ScraperError::FeedParse { url: url.to_string(), message: e.to_string() })?;
+
+    Ok(feed
+        .entries
+        .into_iter()
+        .map(|entry| {
+            // Atom entries can carry several links; the alternate one is the article
+            let link = entry
+                .links
+                .iter()
+                .find(|link| link.rel.as_deref().is_none_or(|rel| rel == "alternate"))
+                .or(entry.links.first())
+                .map(|link| link.href.clone());
+            let content = entry
+                .content
+                .and_then(|content| content.body)
+                .or(entry.summary.map(|summary| summary.content))
+                .filter(|content| !content.trim().is_empty());
+            FeedEntry {
+                title: entry.title.map_or_else(|| "Unknown".to_string(), |title| title.content.trim().to_string()),
+                link,
+                content,
+            }
+        })
+        .collect())
+}
+
+// Analyze every feed entry, at most `concurrency` at a time, returning results in feed order
+// keyed by FeedEntry::key. Entries are analyzed from their inline content unless
+// `fetch_links` is set or they have none, in which case the linked page is scraped with
+// `selector`.
+pub async fn analyze_feed(
+    runner: &LLMRunner,
+    scraper: &Scraper,
+    entries: Vec<FeedEntry>,
+    selector: &str,
+    fetch_links: bool,
+    concurrency: usize,
+    options: &AnalyzeOptions,
+) -> Vec<(String, Result<PageAnalysis, ScraperError>)> {
+    let duplicates = options.dedup.then(DuplicateIndex::default);
+    stream::iter(entries)
+        .map(|entry| {
+            let duplicates = duplicates.as_ref();
+            async move {
+                let result = options
+                    .stop
+                    .run(analyze_entry(runner, scraper, &entry, selector, fetch_links, options, duplicates))
+                    .await;
+                (entry.key().to_string(), result)
+            }
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+async fn analyze_entry(
+    runner: &LLMRunner,
+    scraper: &Scraper,
+    entry: &FeedEntry,
+    selector: &str,
+    fetch_links: bool,
+    options: &AnalyzeOptions,
+    duplicates: Option<&DuplicateIndex>,
+) -> Result<PageAnalysis, ScraperError> {
+    let inline = entry
+        .content
+        .as_deref()
+        .filter(|_| !fetch_links || entry.link.is_none())
+        .map(|content| scraper.html_text(content))
+        .filter(|text| !text.is_empty());
+    let page = match (inline, &entry.link) {
+        (Some(text), _) => ScrapedPage {
+            url: entry.key().to_string(),
+            title: entry.title.clone(),
+            content_parts: vec![text],
+            unmatched_selectors: Vec::new(),
+            auto_extracted: false,
+            broadened_selector: None,
+            metadata: PageMetadata::default(),
+            separator: scraper.separator().to_string(),
+        },
+        (None, Some(link)) => scraper.scrape(link, selector).await?,
+        (None, None) => return Err(ScraperError::EmptyContent { selector: selector.to_string() }),
+    };
+    scraper.ensure_enough_content(&page)?;
+    analyze_scraped(runner, page, options, duplicates).await
+}
@@ -0,0 +1,25 @@
+// lib.rs - Scraping + LLM analysis pipeline, shared by the CLI and other binaries
+
+#[allow(non_snake_case)]
+pub mod LLM_run;
+pub mod batch;
+pub mod cache;
+pub mod clean;
+pub mod crawl;
+pub mod error;
+pub mod feed;
+pub mod markdown;
+pub mod parse;
+pub mod pdf;
+pub mod pipeline;
+pub mod robots;
+pub mod scrape;
+pub mod sitemap;
+pub mod store;
+pub mod template;
+pub mod text;
+pub mod xpath;
+
+pub use crate::error::ScraperError;
+pub use crate::LLM_run::{AnalysisBuilder, AnalysisMode, ContentAnalysis, Entity, GenerationConfig, LLMRunner, SafetySetting, SelectedAnalysis, SentimentResult, Summary, UsageStats};
+pub use crate::scrape::{scrape_content, ScrapedPage, Scraper};
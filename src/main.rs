@@ -1,147 +1,1003 @@
-//mod LLM_runner;
-mod LLM_run;
+mod cli;
+mod output;
 
-use std::env;
+use clap::Parser;
 use dotenv::dotenv;
-use reqwest::Client;
-use scraper::{Html, Selector};
-use serde::{Deserialize, Serialize};
-use tokio::time::{sleep, Duration};
-use std::io::{self, Write};
-//use crate::LLM_run::LLMRunner;
-use crate::LLM_run::{LLMRunner, ContentAnalysis};
+use futures::stream::{self, StreamExt};
+use indicatif::{ProgressBar, ProgressStyle};
+use tracing::{debug, error, info, warn};
+use tracing_subscriber::EnvFilter;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, IsTerminal, Read, Write};
+use std::path::Path;
+use std::slice;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use web_scrapper_project::{AnalysisMode, LLMRunner, ScrapedPage, Scraper, ScraperError};
+use web_scrapper_project::batch::{
+    analyze_urls_stream, analyze_urls_with_progress, parse_url_list, AnalyzeOptions, BatchEvent, BatchReport, PageAnalysis, RelevanceFilter,
+    StopSignal,
+};
+use web_scrapper_project::clean::ContentCleaner;
+use web_scrapper_project::cache::DEFAULT_CACHE_TTL;
+use web_scrapper_project::crawl::{crawl, CrawlOptions};
+use web_scrapper_project::feed::analyze_feed;
+use web_scrapper_project::scrape::{decode_html, filter_same_domain, normalize_url, split_selector_list, FetchedPage};
+use web_scrapper_project::store::ResultStore;
+use web_scrapper_project::template::PromptTemplate;
+use web_scrapper_project::pipeline::{analyze_regions, report_page, Region, ReportOptions};
+use web_scrapper_project::text::{extractive_summary, safe_prefix};
+use crate::cli::{Args, Mode, OutputFormat};
+use crate::output::{print_analysis, print_report, print_selector_test, write_selector_test_csv, print_extractive, write_extractive_csv, ExtractiveRecord, print_answer, print_translation, print_dry_run, print_sentiment, print_usage, write_csv, write_dry_run_csv, DryRunRecord, OutputRecord, print_regions, write_regions_csv, RegionsRecord, write_json_document, write_json_line, JsonDocumentWriter};
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    dotenv().ok();
+// Send diagnostics to stderr, at a level picked by RUST_LOG or else -v/-q
+fn init_logging(args: &Args) {
+    let default_level = match (args.quiet, args.verbose) {
+        (true, _) => "warn",
+        (false, 0) => "info",
+        (false, 1) => "debug",
+        (false, _) => "trace",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_level));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .with_target(false)
+        .without_time()
+        .init();
+}
+
+// Print a prompt on stderr (stdout is kept for results) and read one trimmed line from stdin.
+// None when stdin is closed before a line arrives.
+fn read_input(prompt: &str) -> io::Result<Option<String>> {
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input)? == 0 {
+        eprintln!();
+        return Ok(None);
+    }
+    Ok(Some(input.trim().to_string()))
+}
 
-    print!("Enter the URL: "); 
-    io::stdout().flush()?;
+// Build the LLM runner from the environment and command-line overrides
+fn build_runner(args: &Args) -> Result<LLMRunner, ScraperError> {
+    let mut llm_runner = LLMRunner::from_env()?.with_max_content_chars(args.max_content_chars);
+    if let Some(model) = &args.model {
+        llm_runner = llm_runner.with_model(model)?;
+    }
+    if let Some(path) = &args.prompt_template {
+        llm_runner = llm_runner.with_prompt_template(PromptTemplate::from_file(path)?);
+    }
+    if let Some(instruction) = &args.system_instruction {
+        llm_runner = llm_runner.with_system_instruction(instruction);
+    }
+    if args.include_raw {
+        llm_runner = llm_runner.include_raw(true);
+    }
+    if args.mode == Mode::Composed {
+        llm_runner = llm_runner.with_analysis_mode(AnalysisMode::Composed);
+    }
+    if !args.no_cache {
+        llm_runner = llm_runner.with_cache(args.cache_dir.join("llm"), DEFAULT_CACHE_TTL)?;
+    }
+    Ok(llm_runner)
+}
 
-    let mut url_input = String::new();
-    io::stdin().read_line(&mut url_input); 
-    let mut url = url_input.trim().to_string();
+// Build the page scraper from command-line options
+fn build_scraper(args: &Args) -> Result<Scraper, ScraperError> {
+    let excludes: Vec<&str> = args.excludes.iter().map(String::as_str).collect();
+    let headers = args
+        .headers
+        .iter()
+        .map(|header| match header.split_once(':') {
+            Some((name, value)) => Ok((name.to_string(), value.to_string())),
+            None => Err(ScraperError::Config("Expected --header \"Name: value\", got a value without ':'".to_string())),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
-    if url.is_empty(){
-        println!("No URL provided. Existing "); 
-        return Ok(()); 
+    let mut scraper = Scraper::new()?
+        .with_headers(headers)?
+        .with_max_redirects(args.max_redirects)?
+        .refresh(args.refresh)
+        .markdown(args.markdown)
+        .surrounding_context(args.with_context)
+        .preserve_blocks(args.preserve_blocks)
+        .xpath(args.xpath)
+        .respect_robots(args.respect_robots)
+        .with_delay(Duration::from_secs_f64(args.delay.max(0.0)))
+        .with_min_content_chars(args.min_content_chars)
+        .with_broaden_below(args.broaden_below)
+        .with_max_response_bytes(args.max_response_bytes)
+        .with_excludes(&excludes)?;
+    if let Some(proxy) = &args.proxy {
+        scraper = scraper.with_proxy(proxy)?;
+    }
+    if let Some(user_agent) = &args.user_agent {
+        scraper = scraper.with_user_agent(user_agent);
+    }
+    if let Some(cookie) = &args.cookie {
+        scraper = scraper.with_cookie(cookie)?;
+    }
+    if let Some(separator) = args.separator() {
+        scraper = scraper.with_separator(&separator);
+    }
+    if let Some(heading) = &args.from_heading {
+        scraper = scraper.from_heading(heading);
+    }
+    if args.clean {
+        let mut cleaner = ContentCleaner::default();
+        if let Some(path) = &args.boilerplate {
+            cleaner.add_patterns_file(path)?;
+        }
+        scraper = scraper.with_cleaner(cleaner);
     }
+    if !args.no_cache {
+        scraper = scraper.with_cache(args.cache_dir.join("html"), DEFAULT_CACHE_TTL)?;
+    }
+    Ok(scraper)
+}
 
-    if !url.starts_with("http://") && !url.starts_with("https://") {
-        url = format!("https://{}", url);
-        println!("Auto-corrected URL: {}", url);
-     }
+// Where results go: the --output file, or stdout
+fn open_output(args: &Args) -> io::Result<Box<dyn Write>> {
+    Ok(match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout().lock()),
+    })
+}
 
-     println!("Enter the CSS selector for the main content (e.g., 'article', '.content-body', '#main-text'): ");
-     io::stdout().flush()?; 
-     let mut selector_input = String::new();
-     io::stdin().read_line(&mut selector_input);
-     let content_selector_str = selector_input.trim(); 
+// Progress bar for batch runs, shown only with text output on an interactive terminal so
+// JSON and CSV stay clean for piping
+fn batch_progress_bar(args: &Args, total: usize) -> Option<ProgressBar> {
+    if args.format != OutputFormat::Text || !io::stdout().is_terminal() {
+        return None;
+    }
+    let style = ProgressStyle::with_template("{bar:40.cyan/blue} {pos}/{len} done {prefix:.red} {wide_msg}")
+        .expect("valid progress bar template")
+        .progress_chars("=> ");
+    Some(ProgressBar::new(total as u64).with_style(style))
+}
 
-     if content_selector_str.is_empty(){
-        println!("No content selector provided. Existing ");
-        return Ok(());
-     }
-
-     println!("Please Kindly wait ..."); 
-     println!("Fetching URL: {}", url);
-
-     let client = Client::builder()
-        .user_agent("My Rust Web Scraper with LLM 1.0")
-        .build()?; 
-
-     let response_text = client.get(&url).send().await?.text().await?;
-     println!("Successfully fetched URL: {}", url);
-
-     let document = Html::parse_document(&response_text);
-     println!("HTML parsed successfully");
-
-     let title_selector = Selector::parse("title").unwrap(); 
-     let page_title = document
-        .select(&title_selector)
-        .next()
-        .map(|element| element.text().collect::<String>())
-        .unwrap_or_else(|| "Unknown".to_string());
-
-     println!("Page title: {}", page_title);
-
-     let content_selector = Selector::parse(content_selector_str)
-        .map_err(|e| format!("Failed to parse content selector '{}': {:?}", content_selector_str, e))?;
-     println!("Looking for content elements matching selector: {}", content_selector_str); 
-
-     let mut scrapper_content_parts = Vec::new();
-     for element in document.select(&content_selector) {
-         let text = element.text().collect::<Vec<_>>().join(" ").trim().to_string();
-         if !text.is_empty() {
-             scrapper_content_parts.push(text);
-         }
-     }
-     if scrapper_content_parts.is_empty(){
-        println!("No content found matching selector: '{}'. Cannot perform LLm analysis on selected content", content_selector_str);
+// Where batch mode gets its URLs from
+enum BatchSource<'a> {
+    File(&'a Path),
+    Sitemap(&'a str),
+    Stdin,
+}
+
+// Analyze every URL from `source`, logging and skipping failures
+async fn run_batch(args: &Args, out: &mut dyn Write, source: BatchSource<'_>) -> Result<(), Box<dyn std::error::Error>> {
+    // Without a selector every page goes through readable-content extraction
+    let selector = args.selector().unwrap_or_default();
+
+    let scraper = Arc::new(build_scraper(args)?);
+    let (urls, source) = match source {
+        BatchSource::File(path) => (parse_url_list(&fs::read_to_string(path)?), path.display().to_string()),
+        BatchSource::Sitemap(sitemap_url) => {
+            let sitemap_url = normalize_url(sitemap_url);
+            info!("Fetching sitemap: {}", sitemap_url);
+            (scraper.fetch_sitemap(&sitemap_url).await?, sitemap_url)
+        }
+        BatchSource::Stdin => (parse_url_list(&io::read_to_string(io::stdin())?), "stdin".to_string()),
+    };
+    if urls.is_empty() {
+        info!("No URLs found in {}", source);
         return Ok(());
-     }
-
-     let combined_scrapped_content = scrapper_content_parts.join("\n\n ---- \n\n");
-     println!("Total characters in selected content: {}", combined_scrapped_content.len()); 
-     if combined_scrapped_content.len() > 500 {
-        println!("Snipped of selected content: \n{}...", &combined_scrapped_content[..500]);
-     }else{
-        println!("Selected content: \n{}", combined_scrapped_content);
-     }
-
-    println!("\nInitializing LLM Runner...");
-    match LLMRunner::new() {
-        Ok(llm_runner) => {
-            println!("LLM Runner initialized.");
-
-            // 4. Perform LLM Analysis
-            println!("\nRequesting LLM analysis for the scraped content...");
-            match llm_runner.analyze_web_content(
-                &page_title,
-                &combined_scrapped_content,
-                &url,
-            ).await {
-                Ok(analysis) => {
-                    println!("\n--- LLM Content Analysis ---");
-                    println!("URL: {}", url);
-                    println!("Page Title: {}", page_title);
-                    println!("\nSummary:\n{}", analysis.summary);
-                    println!("\nSentiment:\n{}", analysis.sentiment);
-                    println!("\nKey Topics:\n{}", analysis.key_topics);
-                    println!("\nCategory:\n{}", analysis.category);
-                    println!("--- End of Analysis ---");
+    }
+    info!("Loaded {} URLs from {}", urls.len(), source);
+
+    if args.dry_run {
+        return run_batch_dry_run(args, out, &scraper, urls, selector).await;
+    }
+    let store = args.db.as_deref().map(ResultStore::open).transpose()?;
+    let urls = match &store {
+        Some(store) if !args.refresh => {
+            let mut pending = Vec::with_capacity(urls.len());
+            for url in urls {
+                if !store.contains(&url)? {
+                    pending.push(url);
                 }
-                Err(e) => {
-                    eprintln!("\nError during LLM analysis: {}", e);
+            }
+            if pending.is_empty() {
+                info!("All URLs are already stored in the database; use --refresh to analyze them again");
+                return Ok(());
+            }
+            pending
+        }
+        _ => urls,
+    };
+    let llm_runner = Arc::new(build_runner(args)?);
+    info!("Analyzing with up to {} URLs at a time...", args.concurrency.max(1));
+    if matches!(args.format, OutputFormat::Json | OutputFormat::Jsonl) {
+        stream_results(args, out, &llm_runner, &scraper, urls, selector, store.as_ref()).await?;
+        if args.usage {
+            print_usage(&llm_runner.usage());
+        }
+        return Ok(());
+    }
+    let progress_bar = batch_progress_bar(args, urls.len());
+    let usage_runner = Arc::clone(&llm_runner);
+    let options = analyze_options(args);
+    stop_on_ctrl_c(options.stop.clone());
+    let on_event_bar = progress_bar.clone();
+    let failed = Arc::new(AtomicUsize::new(0));
+    let on_event = move |event: BatchEvent<'_>| {
+        let Some(progress_bar) = &on_event_bar else {
+            return;
+        };
+        match event {
+            BatchEvent::Started { url } => progress_bar.set_message(url.to_string()),
+            BatchEvent::Finished { succeeded, .. } => {
+                if !succeeded {
+                    let failed = failed.fetch_add(1, Ordering::Relaxed) + 1;
+                    progress_bar.set_prefix(format!("{} failed", failed));
                 }
+                progress_bar.inc(1);
             }
+        }
+    };
+    let results =
+        analyze_urls_with_progress(llm_runner, scraper, urls, selector, args.concurrency, options, on_event).await;
+    if let Some(progress_bar) = progress_bar {
+        progress_bar.finish_and_clear();
+    }
 
-            if !combined_scrapped_content.is_empty() {
-                let snippet_for_sentiment = if combined_scrapped_content.len() > 500 {
-                    &combined_scrapped_content[..500]
-                } else {
-                    &combined_scrapped_content
-                };
-                println!("\nRequesting specific sentiment analysis for a snippet...");
-                 match llm_runner.analyze_sentiment(snippet_for_sentiment).await {
-                     Ok(sentiment_result) => {
-                         println!("\n--- LLM Snippet Sentiment Analysis ---");
-                         println!("Label: {}", sentiment_result.label);
-                         println!("Confidence: {:.2}%", sentiment_result.confidence);
-                         println!("Explanation: {}", sentiment_result.explanation);
-                         println!("--- End of Snippet Sentiment Analysis ---");
-                     }
-                     Err(e) => {
-                         eprintln!("\nError during LLM sentiment analysis: {}", e);
-                     }
-                 }
-            }
+    report_results(args, out, &results, store.as_ref(), "Batch")?;
+    if args.usage {
+        print_usage(&usage_runner.usage());
+    }
+    Ok(())
+}
+
+// Per-page analysis options from the command line
+fn analyze_options(args: &Args) -> AnalyzeOptions {
+    AnalyzeOptions {
+        relevance: args.min_relevance.map(|min_score| RelevanceFilter {
+            keywords: args.keywords().into_iter().map(str::to_string).collect(),
+            min_score,
+        }),
+        categories: args.categories().into_iter().map(str::to_string).collect(),
+        dedup: args.dedup,
+        stop: StopSignal::default(),
+        save_content: args.save_content.clone(),
+    }
+}
 
+// Counts behind the summary line logged when a batch, crawl or feed run ends
+#[derive(Default)]
+struct RunTally {
+    succeeded: usize,
+    filtered: usize,
+    duplicates: usize,
+    failed: usize,
+    cancelled: usize,
+}
+
+impl RunTally {
+    fn log(&self, kind: &str) {
+        info!(
+            "{} finished: {} succeeded, {} filtered out, {} duplicates, {} failed",
+            kind, self.succeeded, self.filtered, self.duplicates, self.failed
+        );
+        if self.cancelled > 0 {
+            warn!("{} pages were not analyzed because the run was interrupted", self.cancelled);
+        }
+    }
+}
+
+// Log, count and store the result for one page, print its text block, and return the
+// record written for it in the other formats
+fn record_result<'a>(
+    args: &Args,
+    out: &mut dyn Write,
+    store: Option<&ResultStore>,
+    url: &'a str,
+    result: &'a Result<PageAnalysis, ScraperError>,
+    tally: &mut RunTally,
+) -> io::Result<OutputRecord<'a>> {
+    match result {
+        Ok(result) => {
+            tally.succeeded += 1;
+            for selector in &result.page.unmatched_selectors {
+                warn!("Selector '{}' matched no elements on {}", selector, url);
+            }
+            if result.page.auto_extracted {
+                warn!("Using automatically extracted main content for {}", url);
+            }
+            if let Some(selector) = &result.page.broadened_selector {
+                warn!("Selector matched too little text on {}; using '{}' instead", url, selector);
+            }
+            if args.format == OutputFormat::Text {
+                print_analysis(out, &result.page.url, &result.page.title, &result.analysis)?;
+            }
+            if let Some(store) = store
+                && let Err(e) = store.upsert_analysis(url, &result.page.title, &result.analysis)
+            {
+                error!("Failed to store the result for {}: {}", url, e);
+            }
+            Ok(OutputRecord {
+                url: &result.page.url,
+                title: Some(&result.page.title),
+                analysis: Some(&result.analysis),
+                snippet_sentiment: None,
+                question: None,
+                answer: None,
+                translation: None,
+                error: None,
+            })
         }
         Err(e) => {
-            eprintln!("Failed to initialize LLM Runner: {}", e);
-            eprintln!("Please ensure your GEMINI_API_KEY is set in a .env file or environment variables.");
+            if let ScraperError::FilteredOut { .. } = e {
+                tally.filtered += 1;
+                info!("Skipping {}: {}", url, e);
+            } else if let ScraperError::Duplicate { .. } = e {
+                tally.duplicates += 1;
+                info!("Skipping {}: {}", url, e);
+            } else {
+                tally.failed += 1;
+                warn!("Skipping {}: {}", url, e);
+            }
+            Ok(OutputRecord {
+                url,
+                title: None,
+                analysis: None,
+                snippet_sentiment: None,
+                question: None,
+                answer: None,
+                translation: None,
+                error: Some(e.to_string()),
+            })
+        }
+    }
+}
+
+// Print the analysis of every page in the chosen format, store them when --db is set and
+// log a summary line starting with `kind`
+fn report_results(
+    args: &Args,
+    out: &mut dyn Write,
+    results: &[(String, Result<PageAnalysis, ScraperError>)],
+    store: Option<&ResultStore>,
+    kind: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Pages an interrupt kept from finishing are left out of the output
+    let (cancelled, results): (Vec<_>, Vec<_>) =
+        results.iter().partition(|(_, result)| matches!(result, Err(ScraperError::Cancelled)));
+    let mut tally = RunTally { cancelled: cancelled.len(), ..RunTally::default() };
+    let mut reports = Vec::new();
+    for (i, (url, result)) in results.iter().enumerate() {
+        if args.format == OutputFormat::Text {
+            writeln!(out, "\n=== [{}/{}] {} ===", i + 1, results.len(), url)?;
+        }
+        reports.push(record_result(args, out, store, url, result, &mut tally)?);
+    }
+
+    let report = args.report.then(|| {
+        BatchReport::from_analyses(results.iter().filter_map(|(_, result)| result.as_ref().ok()).map(|page| &page.analysis))
+    });
+    match (args.format, &report) {
+        (OutputFormat::Text, Some(report)) => print_report(report, out)?,
+        (OutputFormat::Text, None) => {}
+        (OutputFormat::Json, report) => write_json_document(out, &reports, report.as_ref())?,
+        (OutputFormat::Jsonl, report) => {
+            for record in &reports {
+                write_json_line(out, record)?;
+            }
+            if let Some(report) = report {
+                print_report(report, &mut io::stderr())?;
+            }
+        }
+        (OutputFormat::Csv, report) => {
+            write_csv(&mut *out, &reports)?;
+            // Keep the CSV itself machine-readable
+            if let Some(report) = report {
+                print_report(report, &mut io::stderr())?;
+            }
+        }
+    }
+    tally.log(kind);
+    Ok(())
+}
+
+// Analyze the batch and write each page the moment it is done, as a JSON line or as the
+// next entry of the JSON document, so output can be consumed while the run is still going.
+// After Ctrl-C the pages left out end the stream early and the document is still closed.
+async fn stream_results(
+    args: &Args,
+    out: &mut dyn Write,
+    runner: &LLMRunner,
+    scraper: &Scraper,
+    urls: Vec<String>,
+    selector: &str,
+    store: Option<&ResultStore>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let options = analyze_options(args);
+    stop_on_ctrl_c(options.stop.clone());
+    let mut document = match args.format {
+        OutputFormat::Json => Some(JsonDocumentWriter::begin(out)?),
+        _ => None,
+    };
+    let mut tally = RunTally::default();
+    // Only the analyses are kept, and only when --report needs them
+    let mut analyses = Vec::new();
+    let mut results = analyze_urls_stream(runner, scraper, urls, selector, args.concurrency, &options);
+    while let Some((url, result)) = results.next().await {
+        if let Err(ScraperError::Cancelled) = result {
+            tally.cancelled += 1;
+            continue;
+        }
+        let record = record_result(args, out, store, &url, &result, &mut tally)?;
+        match &mut document {
+            Some(document) => document.write_result(out, &record)?,
+            None => write_json_line(out, &record)?,
+        }
+        if args.report
+            && let Ok(page) = result
+        {
+            analyses.push(page.analysis);
+        }
+    }
+
+    let report = args.report.then(|| BatchReport::from_analyses(&analyses));
+    match (document, &report) {
+        (Some(document), report) => document.finish(out, report.as_ref())?,
+        (None, Some(report)) => print_report(report, &mut io::stderr())?,
+        (None, None) => {}
+    }
+    tally.log("Batch");
+    Ok(())
+}
+
+// How long pages already in progress get to finish after Ctrl-C
+const INTERRUPT_GRACE: Duration = Duration::from_secs(10);
+
+// On Ctrl-C, start no new pages; after INTERRUPT_GRACE or a second Ctrl-C, abandon the ones
+// in progress as well, so the completed results can still be written out
+fn stop_on_ctrl_c(stop: StopSignal) {
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        warn!(
+            "Interrupted: letting pages in progress finish for up to {}s (Ctrl-C again to stop now)",
+            INTERRUPT_GRACE.as_secs()
+        );
+        stop.stop();
+        tokio::select! {
+            _ = tokio::time::sleep(INTERRUPT_GRACE) => {}
+            _ = tokio::signal::ctrl_c() => {}
         }
+        stop.abort();
+    });
+}
+
+// Analyze every entry of the --feed feed
+async fn run_feed(args: &Args, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(feed_url) = args.feed.as_deref() else {
+        return Ok(());
+    };
+    let feed_url = normalize_url(feed_url);
+    let selector = args.selector().unwrap_or_default();
+    let scraper = build_scraper(args)?;
+    info!("Fetching feed: {}", feed_url);
+    let entries = scraper.fetch_feed(&feed_url).await?;
+    if entries.is_empty() {
+        info!("No entries found in {}", feed_url);
+        return Ok(());
+    }
+    info!("Loaded {} entries from {}", entries.len(), feed_url);
+
+    let store = args.db.as_deref().map(ResultStore::open).transpose()?;
+    let llm_runner = build_runner(args)?;
+    let options = analyze_options(args);
+    stop_on_ctrl_c(options.stop.clone());
+    let results =
+        analyze_feed(&llm_runner, &scraper, entries, selector, args.fetch_links, args.concurrency, &options).await;
+    report_results(args, out, &results, store.as_ref(), "Feed")?;
+    if args.usage {
+        print_usage(&llm_runner.usage());
+    }
+    Ok(())
+}
+
+// Crawl the site starting at args.url and analyze every page found
+async fn run_crawl(args: &Args, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(seed) = args.url.as_deref().map(str::trim).filter(|url| !url.is_empty()) else {
+        return Err("--crawl needs a starting URL".into());
+    };
+    let seed = normalize_url(seed);
+    let selector = args.selector().unwrap_or_default();
+    let scraper = build_scraper(args)?;
+    let store = args.db.as_deref().map(ResultStore::open).transpose()?;
+    let llm_runner = build_runner(args)?;
+    let limits = CrawlOptions {
+        max_depth: args.max_depth,
+        max_pages: args.max_pages,
+        concurrency: args.concurrency,
+    };
+    info!("Crawling {} up to {} links deep and {} pages", seed, limits.max_depth, limits.max_pages);
+
+    let options = analyze_options(args);
+    stop_on_ctrl_c(options.stop.clone());
+    let results: Vec<_> = crawl(&llm_runner, &scraper, &seed, selector, &limits, &options)
+        .await
+        .into_iter()
+        .collect();
+    report_results(args, out, &results, store.as_ref(), "Crawl")?;
+    if args.usage {
+        print_usage(&llm_runner.usage());
+    }
+    Ok(())
+}
+
+// Scrape every URL and report the extracted text without calling the LLM
+async fn run_batch_dry_run(
+    args: &Args,
+    out: &mut dyn Write,
+    scraper: &Scraper,
+    urls: Vec<String>,
+    selector: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let results: Vec<_> = stream::iter(urls)
+        .map(|url| async move {
+            let result = scraper.scrape(&url, selector).await;
+            (url, result)
+        })
+        .buffered(args.concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut failed = 0;
+    let mut records = Vec::new();
+    for (url, result) in &results {
+        match result {
+            Ok(page) => records.push(DryRunRecord::from_page(page)),
+            Err(e) => {
+                failed += 1;
+                warn!("Skipping {}: {}", url, e);
+                records.push(DryRunRecord {
+                    url,
+                    title: None,
+                    characters: 0,
+                    estimated_tokens: 0,
+                    content: None,
+                    error: Some(e.to_string()),
+                });
+            }
+        }
+    }
+
+    match args.format {
+        OutputFormat::Text => {
+            for record in records.iter().filter(|record| record.content.is_some()) {
+                print_dry_run(out, record)?;
+            }
+        }
+        OutputFormat::Json => write_json_document(out, &records, None)?,
+        OutputFormat::Jsonl => {
+            for record in &records {
+                write_json_line(out, record)?;
+            }
+        }
+        OutputFormat::Csv => write_dry_run_csv(out, &records)?,
+    }
+    info!("Dry run finished: {} scraped, {} failed", results.len() - failed, failed);
+    Ok(())
+}
+
+// Print every link found on `url`, one per line
+async fn run_links(args: &Args, out: &mut dyn Write, url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    info!("Fetching URL: {}", url);
+    let page = build_scraper(args)?.fetch(url).await?;
+    if page.final_url != url {
+        info!("Redirected to: {}", page.final_url);
+    }
+
+    let mut links = Scraper::extract_links(&page.html, &page.final_url);
+    if args.same_domain {
+        links = filter_same_domain(&links, &page.final_url);
+    }
+    info!("Found {} links", links.len());
+
+    match args.format {
+        OutputFormat::Json => write_json_document(out, &links, None)?,
+        OutputFormat::Jsonl => {
+            for link in &links {
+                write_json_line(out, link)?;
+            }
+        }
+        OutputFormat::Text | OutputFormat::Csv => {
+            for link in links {
+                writeln!(out, "{}", link)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Confirm the LLM setup works before a real job: a test prompt, then the model's description
+async fn run_check(args: &Args, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+    let mut llm_runner = LLMRunner::from_env()?;
+    if let Some(model) = &args.model {
+        llm_runner = llm_runner.with_model(model)?;
+    }
+    info!("Testing the LLM connection...");
+    if !llm_runner.test_connection().await? {
+        return Err("The LLM answered the test prompt, but not with the expected 'OK'".into());
+    }
+    writeln!(out, "LLM connection OK")?;
+    let model_info = llm_runner.get_model_info().await?;
+    writeln!(out, "\n--- Model Info ---\n{}\n--- End of Model Info ---", model_info.trim())?;
+    Ok(())
+}
+
+// Report how each selector in `selectors` matches on `url`, for --selector-test
+async fn run_selector_test(
+    args: &Args,
+    out: &mut dyn Write,
+    url: &str,
+    selectors: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let selectors = split_selector_list(selectors);
+    if selectors.is_empty() {
+        return Err("--selector-test needs selectors to try, separated by ',' or ';'".into());
+    }
+    let scraper = build_scraper(args)?;
+    let html = if args.stdin_html {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        decode_html(&bytes, "")
+    } else {
+        info!("Fetching URL: {}", url);
+        scraper.fetch(url).await?.html
+    };
+    let selectors: Vec<&str> = selectors.iter().map(String::as_str).collect();
+    let results = scraper.test_selectors(&html, &selectors)?;
+
+    match args.format {
+        OutputFormat::Text => print_selector_test(out, url, &results)?,
+        OutputFormat::Json => write_json_document(out, &results, None)?,
+        OutputFormat::Jsonl => {
+            for result in &results {
+                write_json_line(out, result)?;
+            }
+        }
+        OutputFormat::Csv => write_selector_test_csv(out, &results)?,
+    }
+    Ok(())
+}
+
+// Fetch the page once and analyze each --region separately
+async fn run_regions(args: &Args, out: &mut dyn Write, url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let regions = args.regions.iter().map(|spec| Region::parse(spec)).collect::<Result<Vec<_>, _>>()?;
+    let scraper = build_scraper(args)?;
+    // Built first so a missing API key fails before anything is downloaded
+    let llm_runner = build_runner(args)?;
+    let page = if args.stdin_html {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        FetchedPage { final_url: url.to_string(), html: decode_html(&bytes, ""), pdf_text: None }
+    } else {
+        info!("Fetching URL: {}", url);
+        scraper.fetch(url).await?
+    };
+
+    let reports = analyze_regions(&llm_runner, &scraper, &page, &regions).await;
+    let title = Scraper::parse_title(&page.html);
+    let record = RegionsRecord { url: &page.final_url, title: &title, regions: &reports };
+    match args.format {
+        OutputFormat::Text => print_regions(out, &record)?,
+        OutputFormat::Json => write_json_document(out, slice::from_ref(&record), None)?,
+        OutputFormat::Jsonl => write_json_line(out, &record)?,
+        OutputFormat::Csv => write_regions_csv(out, &record)?,
+    }
+    if args.usage {
+        print_usage(&llm_runner.usage());
+    }
+    Ok(())
+}
+
+// Label for a page read with --stdin-html when no URL is given
+const STDIN_URL: &str = "stdin";
+
+// Scrape and analyze a single page, prompting for anything not given on the command line
+async fn run_single(args: &Args, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+    let url_input = match &args.url {
+        Some(url) => url.trim().to_string(),
+        None if args.stdin_html => STDIN_URL.to_string(),
+        None => match read_input("Enter the URL: ")? {
+            Some(url) => url,
+            None => {
+                info!("No input on stdin. Exiting");
+                return Ok(());
+            }
+        },
+    };
+
+    if url_input.is_empty() {
+        info!("No URL provided. Existing ");
+        return Ok(());
+    }
+
+    let url = if url_input == STDIN_URL { url_input.clone() } else { normalize_url(&url_input) };
+    if url != url_input {
+        info!("Auto-corrected URL: {}", url);
+    }
+
+    if args.links {
+        return run_links(args, out, &url).await;
+    }
+
+    if !args.regions.is_empty() {
+        return run_regions(args, out, &url).await;
+    }
+
+    let content_selector_str = match args.selector() {
+        Some(selector) => selector.to_string(),
+        // stdin holds the document, so there is nothing to prompt with
+        None if args.stdin_html => String::new(),
+        None => match read_input("Enter the CSS selector(s) for the main content, separated by ',' or ';' (e.g., 'article', '.content-body', '#main-text'), or leave blank to detect it automatically: ")? {
+            Some(selector) => selector,
+            None => {
+                info!("No input on stdin. Exiting");
+                return Ok(());
+            }
+        },
+    };
+
+    if args.selector_test {
+        return run_selector_test(args, out, &url, &content_selector_str).await;
+    }
+
+    let store = match &args.db {
+        Some(path) if !args.dry_run => Some(ResultStore::open(path)?),
+        _ => None,
+    };
+    if let Some(store) = &store
+        && !args.refresh
+        && store.contains(&url)?
+    {
+        info!("{} is already stored in the database; use --refresh to analyze it again", url);
+        return Ok(());
+    }
+
+    // Built before fetching so a missing API key fails before anything is downloaded;
+    // --dry-run and --extractive never call the LLM
+    let llm_runner = if args.dry_run || args.extractive {
+        None
+    } else {
+        info!("Initializing LLM Runner...");
+        let llm_runner = build_runner(args)?;
+        info!("LLM Runner initialized.");
+        Some(llm_runner)
+    };
+
+    info!("Please Kindly wait ...");
+    let scraper = build_scraper(args)?;
+    let scraped = if args.stdin_html {
+        info!("Reading HTML from stdin");
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        let fetched = FetchedPage { final_url: url.clone(), html: decode_html(&bytes, ""), pdf_text: None };
+        scraper.scrape_fetched(fetched, &content_selector_str)
+    } else {
+        info!("Fetching URL: {}", url);
+        scraper.scrape(&url, &content_selector_str).await
+    };
+    let page = match scraped {
+        Ok(page) => page,
+        Err(ScraperError::EmptyContent { selector }) => {
+            warn!("No content found matching selector: '{}', and no main content could be detected. Cannot perform LLm analysis on selected content", selector);
+            return Ok(());
+        }
+        Err(e @ ScraperError::InsufficientContent { .. }) => {
+            warn!("{}. Skipping LLM analysis", e);
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    info!("Successfully fetched URL: {}", url);
+    if page.url != url {
+        info!("Redirected to: {}", page.url);
+    }
+    let requested_url = url;
+    let url = page.url.clone();
+    info!("Page title: {}", page.title);
+    for selector in &page.unmatched_selectors {
+        warn!("Selector '{}' matched no elements", selector);
+    }
+    if page.auto_extracted {
+        warn!("Using automatically extracted main content");
+    }
+    if let Some(selector) = &page.broadened_selector {
+        warn!("Selector matched too little text; using '{}' instead", selector);
+    }
+
+    let combined_scrapped_content = page.combined_content();
+    info!("Total characters in selected content: {}", combined_scrapped_content.len());
+    if combined_scrapped_content.len() > 500 {
+        debug!("Snipped of selected content: \n{}...", safe_prefix(&combined_scrapped_content, 500));
+    } else {
+        debug!("Selected content: \n{}", combined_scrapped_content);
+    }
+
+    if args.dry_run {
+        let record = DryRunRecord::from_page(&page);
+        match args.format {
+            OutputFormat::Text => print_dry_run(out, &record)?,
+            OutputFormat::Json => write_json_document(out, slice::from_ref(&record), None)?,
+            OutputFormat::Jsonl => write_json_line(out, &record)?,
+            OutputFormat::Csv => write_dry_run_csv(out, &[record])?,
+        }
+        return Ok(());
+    }
+
+    if args.extractive {
+        let keywords = args.keywords();
+        let record = ExtractiveRecord {
+            url: &url,
+            title: &page.title,
+            keywords: &keywords,
+            sentences: extractive_summary(&combined_scrapped_content, &keywords, args.sentences),
+        };
+        match args.format {
+            OutputFormat::Text => print_extractive(out, &record)?,
+            OutputFormat::Json => write_json_document(out, slice::from_ref(&record), None)?,
+            OutputFormat::Jsonl => write_json_line(out, &record)?,
+            OutputFormat::Csv => write_extractive_csv(out, &record)?,
+        }
+        return Ok(());
+    }
+
+    let Some(llm_runner) = llm_runner else {
+        return Ok(());
+    };
+    if args.repl {
+        return run_repl(out, &llm_runner, &page).await;
+    }
+
+    let options = ReportOptions {
+        selector: content_selector_str,
+        translate_to: args.translate.clone(),
+        categories: args.categories().into_iter().map(str::to_string).collect(),
+        snippet_sentiment: args.snippet_sentiment,
+        question: args.ask.clone(),
+        save_content: args.save_content.clone(),
+        ..ReportOptions::default()
+    };
+    let report = report_page(&llm_runner, &page, &options).await;
+    if args.format == OutputFormat::Text {
+        if let Some(translation) = &report.translation {
+            print_translation(out, translation)?;
+        }
+        if let Some(analysis) = &report.analysis {
+            print_analysis(out, &url, &page.title, analysis)?;
+        }
+        if let Some(sentiment_result) = &report.snippet_sentiment {
+            print_sentiment(out, sentiment_result)?;
+        }
+        if let (Some(question), Some(answer)) = (&options.question, &report.answer) {
+            print_answer(out, question.trim(), answer)?;
+        }
+    }
+    if let (Some(store), Some(analysis)) = (&store, &report.analysis)
+        && let Err(e) = store.upsert_analysis(&requested_url, &page.title, analysis)
+    {
+        error!("Failed to store the result: {}", e);
+    }
+
+    let record = OutputRecord {
+        url: &url,
+        title: Some(&page.title),
+        analysis: report.analysis.as_ref(),
+        snippet_sentiment: report.snippet_sentiment.as_ref(),
+        question: options.question.as_deref().map(str::trim).filter(|question| !question.is_empty()),
+        answer: report.answer.as_deref(),
+        translation: report.translation.as_ref(),
+        error: (!report.errors.is_empty()).then(|| report.errors.join("; ")),
+    };
+    match args.format {
+        OutputFormat::Text => {}
+        OutputFormat::Json => write_json_document(out, slice::from_ref(&record), None)?,
+        OutputFormat::Jsonl => write_json_line(out, &record)?,
+        OutputFormat::Csv => write_csv(&mut *out, &[record])?,
+    }
+    if args.usage {
+        print_usage(&llm_runner.usage());
+    }
+    Ok(())
+}
+
+const REPL_HELP: &str = "Commands:
+  analyze                 full analysis (summary, sentiment, topics, category)
+  summary [N]             summary in N sentences (default 3)
+  topics [N]              up to N key topics (default 5)
+  sentiment               sentiment of the content
+  category [A,B,...]      classify into the given categories (default: built-in list)
+  relevance A,B,...       0-100 relevance to the given keywords
+  ask QUESTION            answer a question from the content
+  help                    show this list
+  quit                    exit";
+
+// Read commands from stdin and answer them from the already scraped `page` until quit or
+// end of input. A failed command is logged and the loop goes on.
+async fn run_repl(
+    out: &mut dyn Write,
+    runner: &LLMRunner,
+    page: &ScrapedPage,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = page.combined_content();
+    eprintln!("Loaded {} ({} characters). Type 'help' for commands.", page.url, content.len());
+    while let Some(line) = read_input("> ")? {
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((&line, ""));
+        let command = command.to_ascii_lowercase();
+        let rest = rest.trim();
+        if command == "quit" || command == "exit" {
+            break;
+        }
+        let list = || -> Vec<&str> { rest.split(',').map(str::trim).filter(|item| !item.is_empty()).collect() };
+        let count = |default: u32| rest.parse::<u32>().ok().filter(|n| *n > 0).unwrap_or(default);
+        let result: Result<(), Box<dyn std::error::Error>> = async {
+            match command.as_str() {
+                "" => {}
+                "help" => writeln!(out, "{}", REPL_HELP)?,
+                "analyze" => {
+                    let analysis = runner
+                        .analyze_web_content_with_context(&page.title, &content, &page.url, page.analysis_context())
+                        .await?;
+                    print_analysis(out, &page.url, &page.title, &analysis)?;
+                }
+                "summary" => writeln!(out, "{}", runner.summarize_content(&content, count(3)).await?.text.trim())?,
+                "topics" => {
+                    for topic in runner.extract_topics(&content, count(5)).await? {
+                        writeln!(out, "- {}", topic.trim_start_matches(['-', '*', ' ']))?;
+                    }
+                }
+                "sentiment" => {
+                    let sentiment = runner.analyze_sentiment(runner.truncate_content(&content)).await?;
+                    writeln!(out, "{} - {}", sentiment.label, sentiment.explanation.trim())?;
+                }
+                "category" => writeln!(out, "{}", runner.classify_content(&page.title, &content, &list()).await?)?,
+                "relevance" if list().is_empty() => eprintln!("Usage: relevance KEYWORD,KEYWORD,..."),
+                "relevance" => writeln!(out, "{:.0}", runner.check_relevance(&content, &list()).await?)?,
+                "ask" if rest.is_empty() => eprintln!("Usage: ask QUESTION"),
+                "ask" => writeln!(out, "{}", runner.answer_question(&content, rest).await?.trim())?,
+                other => eprintln!("Unknown command '{}'. Type 'help' for commands.", other),
+            }
+            Ok(())
+        }
+        .await;
+        if let Err(e) = result {
+            error!("{} failed: {}", command, e);
+        }
+        out.flush()?;
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+    let args = Args::parse();
+    init_logging(&args);
+
+    if (args.stdin_html || args.stdin_urls) && io::stdin().is_terminal() {
+        return Err("--stdin-html and --stdin-urls expect input piped on stdin".into());
+    }
+
+    let mut out = open_output(&args)?;
+    let out = out.as_mut();
+    match (&args.urls_file, &args.sitemap) {
+        _ if args.check => run_check(&args, out).await?,
+        (Some(path), _) => run_batch(&args, out, BatchSource::File(path)).await?,
+        (None, Some(sitemap_url)) => run_batch(&args, out, BatchSource::Sitemap(sitemap_url)).await?,
+        (None, None) if args.stdin_urls => run_batch(&args, out, BatchSource::Stdin).await?,
+        (None, None) if args.crawl => run_crawl(&args, out).await?,
+        (None, None) if args.feed.is_some() => run_feed(&args, out).await?,
+        (None, None) => run_single(&args, out).await?,
     }
+    out.flush()?;
     Ok(())
 }
@@ -0,0 +1,209 @@
+// markdown.rs - Renders matched HTML as Markdown so headings, lists, links and code survive
+
+use std::collections::HashSet;
+use ego_tree::{NodeId, NodeRef};
+use scraper::{ElementRef, Node};
+
+// Markdown rendering of `element` and everything under it
+pub fn html_to_markdown(element: ElementRef<'_>) -> String {
+    render_markdown(element, &HashSet::new())
+}
+
+// Like html_to_markdown, but leaves out the subtrees rooted at `skip`
+pub(crate) fn render_markdown(element: ElementRef<'_>, skip: &HashSet<NodeId>) -> String {
+    let mut renderer = Renderer::new(skip, false);
+    renderer.node(*element);
+    tidy(&renderer.out)
+}
+
+struct Renderer<'a> {
+    out: String,
+    skip: &'a HashSet<NodeId>,
+    // Inside <pre>: keep whitespace as-is and don't add inline markup
+    preformatted: bool,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(skip: &'a HashSet<NodeId>, preformatted: bool) -> Self {
+        Self { out: String::new(), skip, preformatted }
+    }
+
+    fn node(&mut self, node: NodeRef<'_, Node>) {
+        if self.skip.contains(&node.id()) {
+            return;
+        }
+        match node.value() {
+            Node::Text(text) => self.text(text),
+            Node::Element(element) => self.element(node, element.name(), element.attr("href")),
+            _ => {}
+        }
+    }
+
+    fn children(&mut self, node: NodeRef<'_, Node>) {
+        for child in node.children() {
+            self.node(child);
+        }
+    }
+
+    fn element(&mut self, node: NodeRef<'_, Node>, name: &str, href: Option<&str>) {
+        match name {
+            "script" | "style" | "noscript" | "template" | "head" | "img" => {}
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse().unwrap_or(1);
+                let text = self.inline(node);
+                if !text.is_empty() {
+                    self.block(&format!("{} {}", "#".repeat(level), text));
+                }
+            }
+            "br" => self.out.push('\n'),
+            "hr" => self.block("---"),
+            "a" => {
+                let text = self.inline(node);
+                match href.map(str::trim) {
+                    Some(href) if !text.is_empty() && !href.is_empty() && !self.preformatted => {
+                        self.out.push_str(&format!("[{}]({})", text, href));
+                    }
+                    _ => self.out.push_str(&text),
+                }
+            }
+            "strong" | "b" => self.wrap(node, "**"),
+            "em" | "i" => self.wrap(node, "*"),
+            "code" => self.wrap(node, "`"),
+            "pre" => {
+                let mut code = Renderer::new(self.skip, true);
+                code.children(node);
+                let code = code.out.trim_matches('\n');
+                if !code.trim().is_empty() {
+                    self.block(&format!("```\n{}\n```", code));
+                }
+            }
+            "ul" | "ol" => self.list(node, name == "ol"),
+            "blockquote" => {
+                let quoted = self
+                    .render_children(node)
+                    .lines()
+                    .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                if !quoted.is_empty() {
+                    self.block(&quoted);
+                }
+            }
+            "td" | "th" => {
+                self.children(node);
+                self.out.push(' ');
+            }
+            "p" | "div" | "section" | "article" | "main" | "header" | "footer" | "aside" | "nav"
+            | "figure" | "figcaption" | "table" | "tr" | "dl" | "dt" | "dd" | "form" | "li" => {
+                self.break_block();
+                self.children(node);
+                self.break_block();
+            }
+            _ => self.children(node),
+        }
+    }
+
+    // Collapse whitespace runs to a single space, as a browser would
+    fn text(&mut self, text: &str) {
+        if self.preformatted {
+            self.out.push_str(text);
+            return;
+        }
+        for c in text.chars() {
+            if !c.is_whitespace() {
+                self.out.push(c);
+            } else if !self.out.is_empty() && !self.out.ends_with(char::is_whitespace) {
+                self.out.push(' ');
+            }
+        }
+    }
+
+    fn wrap(&mut self, node: NodeRef<'_, Node>, marker: &str) {
+        if self.preformatted {
+            self.children(node);
+            return;
+        }
+        let text = self.inline(node);
+        if !text.is_empty() {
+            self.out.push_str(&format!("{marker}{text}{marker}"));
+        }
+    }
+
+    fn list(&mut self, node: NodeRef<'_, Node>, ordered: bool) {
+        let mut items = Vec::new();
+        for child in node.children() {
+            if self.skip.contains(&child.id()) {
+                continue;
+            }
+            if !matches!(child.value(), Node::Element(element) if element.name() == "li") {
+                continue;
+            }
+            let item = self.render_children(child);
+            let mut lines = item.lines().filter(|line| !line.trim().is_empty());
+            let Some(first) = lines.next() else {
+                continue;
+            };
+
+            let marker = if ordered { format!("{}. ", items.len() + 1) } else { "- ".to_string() };
+            let indent = " ".repeat(marker.len());
+            let mut rendered = format!("{}{}", marker, first);
+            for line in lines {
+                rendered.push('\n');
+                rendered.push_str(&indent);
+                rendered.push_str(line);
+            }
+            items.push(rendered);
+        }
+        if !items.is_empty() {
+            self.block(&items.join("\n"));
+        }
+    }
+
+    // Children of `node` rendered on their own and tidied
+    fn render_children(&self, node: NodeRef<'_, Node>) -> String {
+        let mut renderer = Renderer::new(self.skip, self.preformatted);
+        renderer.children(node);
+        tidy(&renderer.out)
+    }
+
+    // Children of `node` flattened onto one line
+    fn inline(&self, node: NodeRef<'_, Node>) -> String {
+        self.render_children(node).split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    fn block(&mut self, text: &str) {
+        self.break_block();
+        self.out.push_str(text);
+        self.break_block();
+    }
+
+    // End the current paragraph with exactly one blank line
+    fn break_block(&mut self) {
+        while self.out.ends_with(' ') {
+            self.out.pop();
+        }
+        if self.out.is_empty() {
+            return;
+        }
+        while !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+}
+
+// Strip trailing spaces and collapse runs of blank lines outside code fences
+fn tidy(markdown: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut in_fence = false;
+    for line in markdown.lines() {
+        let line = line.trim_end();
+        if line.starts_with("```") {
+            in_fence = !in_fence;
+        }
+        if !in_fence && line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    lines.join("\n").trim().to_string()
+}
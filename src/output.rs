@@ -0,0 +1,411 @@
+// output.rs - Formats analysis results for the terminal, JSON and CSV
+
+use serde::Serialize;
+use std::io;
+use web_scrapper_project::batch::BatchReport;
+use web_scrapper_project::pipeline::{RegionReport, Translation};
+use web_scrapper_project::scrape::SelectorMatch;
+use web_scrapper_project::text::{estimate_tokens, highlight_keywords, truncate_chars};
+use web_scrapper_project::{ContentAnalysis, ScrapedPage, SentimentResult, UsageStats};
+
+// Full result for one page, as written in --format json and csv
+#[derive(Serialize)]
+pub struct OutputRecord<'a> {
+    pub url: &'a str,
+    pub title: Option<&'a str>,
+    pub analysis: Option<&'a ContentAnalysis>,
+    pub snippet_sentiment: Option<&'a SentimentResult>,
+    pub question: Option<&'a str>,
+    pub answer: Option<&'a str>,
+    pub translation: Option<&'a Translation>,
+    pub error: Option<String>,
+}
+
+// A page analyzed region by region with --region
+#[derive(Serialize)]
+pub struct RegionsRecord<'a> {
+    pub url: &'a str,
+    pub title: &'a str,
+    pub regions: &'a [RegionReport],
+}
+
+// What --dry-run reports for one page: the text that would be sent to the LLM
+#[derive(Serialize)]
+pub struct DryRunRecord<'a> {
+    pub url: &'a str,
+    pub title: Option<&'a str>,
+    pub characters: usize,
+    pub estimated_tokens: usize,
+    pub content: Option<String>,
+    pub error: Option<String>,
+}
+
+impl<'a> DryRunRecord<'a> {
+    pub fn from_page(page: &'a ScrapedPage) -> Self {
+        let content = page.combined_content();
+        Self {
+            url: &page.url,
+            title: Some(&page.title),
+            characters: content.chars().count(),
+            estimated_tokens: estimate_tokens(&content),
+            content: Some(content),
+            error: None,
+        }
+    }
+}
+
+// Print the extracted content block for --dry-run
+pub fn print_dry_run(out: &mut dyn io::Write, record: &DryRunRecord<'_>) -> io::Result<()> {
+    writeln!(out, "\n--- Extracted Content (dry run) ---")?;
+    writeln!(out, "URL: {}", record.url)?;
+    writeln!(out, "Page Title: {}", record.title.unwrap_or_default())?;
+    writeln!(out, "Characters: {}", record.characters)?;
+    writeln!(out, "Estimated tokens: {}", record.estimated_tokens)?;
+    writeln!(out, "\n{}", record.content.as_deref().unwrap_or_default())?;
+    writeln!(out, "--- End of Extracted Content ---")
+}
+
+// Write one CSV row per dry-run page, with a header
+pub fn write_dry_run_csv<W: io::Write>(writer: W, rows: &[DryRunRecord<'_>]) -> Result<(), csv::Error> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record(["url", "title", "characters", "estimated_tokens", "content"])?;
+
+    for row in rows {
+        if let Some(content) = &row.content {
+            csv_writer.write_record([
+                row.url,
+                row.title.unwrap_or_default(),
+                &row.characters.to_string(),
+                &row.estimated_tokens.to_string(),
+                content,
+            ])?;
+        }
+    }
+
+    csv_writer.flush()?;
+    Ok(())
+}
+
+// What --extractive reports for one page
+#[derive(Serialize)]
+pub struct ExtractiveRecord<'a> {
+    pub url: &'a str,
+    pub title: &'a str,
+    pub keywords: &'a [&'a str],
+    pub sentences: Vec<&'a str>,
+}
+
+// Print the extractive summary, with keywords in bold
+pub fn print_extractive(out: &mut dyn io::Write, record: &ExtractiveRecord<'_>) -> io::Result<()> {
+    writeln!(out, "\n--- Extractive Summary ---")?;
+    writeln!(out, "URL: {}", record.url)?;
+    writeln!(out, "Page Title: {}", record.title)?;
+    writeln!(out)?;
+    for sentence in &record.sentences {
+        writeln!(out, "- {}", highlight_keywords(sentence, record.keywords))?;
+    }
+    writeln!(out, "--- End of Extractive Summary ---")
+}
+
+// Write the extractive summary as a CSV row, with a header
+pub fn write_extractive_csv<W: io::Write>(writer: W, record: &ExtractiveRecord<'_>) -> Result<(), csv::Error> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record(["url", "title", "summary"])?;
+    csv_writer.write_record([record.url, record.title, &record.sentences.join(" ")])?;
+    csv_writer.flush()?;
+    Ok(())
+}
+
+// Characters of the first match shown by --selector-test
+const SELECTOR_PREVIEW_CHARS: usize = 80;
+
+// Print the --selector-test results, one selector per line with a preview of its first match
+pub fn print_selector_test(out: &mut dyn io::Write, url: &str, results: &[SelectorMatch]) -> io::Result<()> {
+    writeln!(out, "\n--- Selector Test ---")?;
+    writeln!(out, "URL: {}", url)?;
+    let width = results.iter().map(|result| result.selector.chars().count()).chain(["Selector".len()]).max().unwrap_or(0);
+    writeln!(out, "\n{:<width$}  Matches  First match", "Selector")?;
+    for result in results {
+        let line = format!("{:<width$}  {:>7}  {}", result.selector, result.matches, selector_preview(result));
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    writeln!(out, "--- End of Selector Test ---")
+}
+
+// Write one CSV row per tested selector, with a header
+pub fn write_selector_test_csv<W: io::Write>(writer: W, results: &[SelectorMatch]) -> Result<(), csv::Error> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record(["selector", "matches", "first_match"])?;
+    for result in results {
+        csv_writer.write_record([&result.selector, &result.matches.to_string(), &selector_preview(result)])?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+// First match on one line, cut to SELECTOR_PREVIEW_CHARS
+fn selector_preview(result: &SelectorMatch) -> String {
+    let text = result.first_text.as_deref().unwrap_or_default().split_whitespace().collect::<Vec<_>>().join(" ");
+    let preview = truncate_chars(&text, SELECTOR_PREVIEW_CHARS);
+    if preview.len() < text.len() { format!("{}...", preview) } else { text }
+}
+
+// Print each region's analysis, or why it has none, under the region's name
+pub fn print_regions(out: &mut dyn io::Write, record: &RegionsRecord<'_>) -> io::Result<()> {
+    for region in record.regions {
+        writeln!(out, "\n=== Region: {} ({}) ===", region.name, region.selector)?;
+        match (&region.analysis, &region.error) {
+            (Some(analysis), _) => print_analysis(out, record.url, record.title, analysis)?,
+            (None, error) => writeln!(out, "Error: {}", error.as_deref().unwrap_or("no analysis"))?,
+        }
+    }
+    Ok(())
+}
+
+// Write one CSV row per analyzed region, with a header
+pub fn write_regions_csv<W: io::Write>(writer: W, record: &RegionsRecord<'_>) -> Result<(), csv::Error> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record(["url", "title", "region", "selector", "category", "sentiment", "key_topics", "summary", "language"])?;
+    for region in record.regions {
+        if let Some(analysis) = &region.analysis {
+            csv_writer.write_record([
+                record.url,
+                record.title,
+                &region.name,
+                &region.selector,
+                &analysis.category,
+                &analysis.sentiment,
+                &analysis.key_topics,
+                &analysis.summary,
+                &analysis.language,
+            ])?;
+        }
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+// Print the human-readable analysis block for one page
+pub fn print_analysis(out: &mut dyn io::Write, url: &str, title: &str, analysis: &ContentAnalysis) -> io::Result<()> {
+    writeln!(out, "\n--- LLM Content Analysis ---")?;
+    writeln!(out, "URL: {}", url)?;
+    writeln!(out, "Page Title: {}", title)?;
+    writeln!(out, "\nSummary:\n{}", analysis.summary)?;
+    writeln!(out, "\nSentiment:\n{}", analysis.sentiment)?;
+    writeln!(out, "\nKey Topics:\n{}", analysis.key_topics)?;
+    writeln!(out, "\nCategory:\n{}", analysis.category)?;
+    for (label, text) in &analysis.extra_sections {
+        writeln!(out, "\n{}:\n{}", label, text)?;
+    }
+    writeln!(out, "\nLanguage: {}", analysis.language)?;
+    if let Some(raw) = &analysis.raw {
+        writeln!(out, "\nRaw LLM Response:\n{}", raw)?;
+    }
+    writeln!(out, "--- End of Analysis ---")
+}
+
+// Print the snippet sentiment block
+pub fn print_sentiment(out: &mut dyn io::Write, sentiment_result: &SentimentResult) -> io::Result<()> {
+    writeln!(out, "\n--- LLM Snippet Sentiment Analysis ---")?;
+    writeln!(out, "Label: {}", sentiment_result.label)?;
+    match sentiment_result.confidence {
+        Some(confidence) => writeln!(out, "Confidence: {:.2}%", confidence)?,
+        None => writeln!(out, "Confidence: unknown")?,
+    }
+    writeln!(out, "Explanation: {}", sentiment_result.explanation)?;
+    writeln!(out, "--- End of Snippet Sentiment Analysis ---")
+}
+
+// Note that the analysis below ran on translated text
+pub fn print_translation(out: &mut dyn io::Write, translation: &Translation) -> io::Result<()> {
+    writeln!(
+        out,
+        "\nContent translated from {} to {} before analysis",
+        translation.source_language, translation.target_language
+    )
+}
+
+// Print the answer to an --ask question
+pub fn print_answer(out: &mut dyn io::Write, question: &str, answer: &str) -> io::Result<()> {
+    writeln!(out, "\n--- LLM Answer ---")?;
+    writeln!(out, "Question: {}", question)?;
+    writeln!(out, "Answer: {}", answer)?;
+    writeln!(out, "--- End of Answer ---")
+}
+
+// Print the --usage summary on stderr so it never mixes with JSON/CSV results
+pub fn print_usage(usage: &UsageStats) {
+    eprintln!("\n--- LLM Usage ---");
+    eprintln!("Requests: {}", usage.request_count);
+    eprintln!("Prompt: {} chars, ~{} tokens", usage.prompt_chars, usage.estimated_prompt_tokens);
+    eprintln!("Response: {} chars, ~{} tokens", usage.response_chars, usage.estimated_response_tokens);
+    eprintln!(
+        "Total: ~{} tokens",
+        usage.estimated_prompt_tokens + usage.estimated_response_tokens
+    );
+    eprintln!("--- End of LLM Usage ---");
+}
+
+// Print the --report overview as small tables
+pub fn print_report(report: &BatchReport, out: &mut dyn io::Write) -> io::Result<()> {
+    writeln!(out, "\n--- Batch Report ({} pages analyzed) ---", report.pages)?;
+    write_table(out, "Category", report.categories.iter().map(|(name, count)| (name.as_str(), *count)))?;
+    write_table(out, "Sentiment", report.sentiments.iter().map(|(name, count)| (name.as_str(), *count)))?;
+    write_table(out, "Top topic", report.top_topics.iter().map(|(name, count)| (name.as_str(), *count)))?;
+    writeln!(out, "--- End of Batch Report ---")
+}
+
+fn write_table<'a>(
+    out: &mut dyn io::Write,
+    heading: &str,
+    rows: impl Iterator<Item = (&'a str, usize)> + Clone,
+) -> io::Result<()> {
+    let width = rows.clone().map(|(name, _)| name.chars().count()).chain([heading.len()]).max().unwrap_or(0);
+    writeln!(out, "\n{:<width$}  Pages", heading)?;
+    for (name, count) in rows {
+        writeln!(out, "{:<width$}  {:>5}", name, count)?;
+    }
+    Ok(())
+}
+
+// Version of the --format json document layout. Bump it when a field is removed or renamed
+// or its meaning changes; adding a field doesn't need a bump. Version 1 looks like:
+//
+//     {
+//       "schema_version": 1,
+//       "scraper_version": "0.1.0",
+//       "results": [ ... ],
+//       "summary": { ... }
+//     }
+//
+// `results` always holds one entry per page (a single page run gives one), or per link
+// with --links and per selector with --selector-test. `summary` is the BatchReport and is
+// only present with --report.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+// Writes a --format json document one result at a time: the opening when created, each
+// result as it is added (flushed, so a batch can be read while it runs) and the closing
+// with the summary in finish. The output is the same as serializing the whole document.
+pub struct JsonDocumentWriter {
+    results: usize,
+}
+
+impl JsonDocumentWriter {
+    pub fn begin(out: &mut dyn io::Write) -> io::Result<Self> {
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"schema_version\": {},", JSON_SCHEMA_VERSION)?;
+        writeln!(out, "  \"scraper_version\": {},", serde_json::to_string(env!("CARGO_PKG_VERSION"))?)?;
+        write!(out, "  \"results\": [")?;
+        out.flush()?;
+        Ok(Self { results: 0 })
+    }
+
+    pub fn write_result<T: Serialize>(&mut self, out: &mut dyn io::Write, result: &T) -> io::Result<()> {
+        let separator = if self.results == 0 { "" } else { "," };
+        write!(out, "{}\n{}", separator, indent(&serde_json::to_string_pretty(result)?, "    "))?;
+        self.results += 1;
+        out.flush()
+    }
+
+    pub fn finish(self, out: &mut dyn io::Write, summary: Option<&BatchReport>) -> io::Result<()> {
+        if self.results > 0 {
+            write!(out, "\n  ")?;
+        }
+        write!(out, "]")?;
+        if let Some(summary) = summary {
+            write!(out, ",\n  \"summary\": {}", indent(&serde_json::to_string_pretty(summary)?, "  ").trim_start())?;
+        }
+        writeln!(out, "\n}}")?;
+        out.flush()
+    }
+}
+
+// Prefix every line of `text` with `prefix`. Safe for pretty-printed JSON, whose strings
+// never hold a raw newline.
+fn indent(text: &str, prefix: &str) -> String {
+    text.lines().map(|line| format!("{}{}", prefix, line)).collect::<Vec<_>>().join("\n")
+}
+
+// Write `results` (and the batch summary, if any) as a --format json document
+pub fn write_json_document<T: Serialize>(
+    out: &mut dyn io::Write,
+    results: &[T],
+    summary: Option<&BatchReport>,
+) -> io::Result<()> {
+    let mut document = JsonDocumentWriter::begin(out)?;
+    for result in results {
+        document.write_result(out, result)?;
+    }
+    document.finish(out, summary)
+}
+
+// Write `value` as one compact JSON line and flush it, for --format jsonl
+pub fn write_json_line<T: Serialize + ?Sized>(out: &mut dyn io::Write, value: &T) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, value)?;
+    writeln!(out)?;
+    out.flush()
+}
+
+// Write one CSV row per analyzed page, with a header
+pub fn write_csv<W: io::Write>(writer: W, rows: &[OutputRecord<'_>]) -> Result<(), csv::Error> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record(["url", "title", "category", "sentiment", "key_topics", "summary", "language"])?;
+
+    for row in rows {
+        if let Some(analysis) = row.analysis {
+            csv_writer.write_record([
+                row.url,
+                row.title.unwrap_or_default(),
+                &analysis.category,
+                &analysis.sentiment,
+                &analysis.key_topics,
+                &analysis.summary,
+                &analysis.language,
+            ])?;
+        }
+    }
+
+    csv_writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[derive(Serialize)]
+    struct JsonDocument<'a> {
+        schema_version: u32,
+        scraper_version: &'static str,
+        results: &'a [Value],
+        #[serde(skip_serializing_if = "Option::is_none")]
+        summary: Option<&'a BatchReport>,
+    }
+
+    fn expected(results: &[Value], summary: Option<&BatchReport>) -> String {
+        let document = JsonDocument {
+            schema_version: JSON_SCHEMA_VERSION,
+            scraper_version: env!("CARGO_PKG_VERSION"),
+            results,
+            summary,
+        };
+        format!("{}\n", serde_json::to_string_pretty(&document).unwrap())
+    }
+
+    fn written(results: &[Value], summary: Option<&BatchReport>) -> String {
+        let mut out = Vec::new();
+        write_json_document(&mut out, results, summary).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn json_document_matches_serializing_it_whole() {
+        let results = [json!({"url": "https://a.example", "tags": ["x", "y"]}), json!({"url": "https://b.example", "error": "line\nbreak"})];
+        let summary = BatchReport::from_analyses(std::iter::empty());
+        assert_eq!(written(&results, None), expected(&results, None));
+        assert_eq!(written(&results, Some(&summary)), expected(&results, Some(&summary)));
+        assert_eq!(written(&[], None), expected(&[], None));
+        assert_eq!(written(&[], Some(&summary)), expected(&[], Some(&summary)));
+    }
+}
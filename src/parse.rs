@@ -0,0 +1,95 @@
+// parse.rs - Helpers for pulling structured fields out of free-form LLM responses
+
+use std::collections::HashMap;
+
+// Remove leading markdown decoration (headings, bullets, quotes, bold markers)
+fn strip_markdown(line: &str) -> (String, bool) {
+    let trimmed = line.trim();
+    let stripped = trimmed.trim_start_matches(|c: char| {
+        matches!(c, '#' | '-' | '*' | '•' | '>' | '+') || c.is_whitespace()
+    });
+    let is_bullet = stripped.len() != trimmed.len() && !trimmed.starts_with("**");
+    (stripped.replace("**", "").replace("__", "").trim().to_string(), is_bullet)
+}
+
+// If `line` starts with one of `labels` (case-insensitive) followed by a colon or nothing,
+// return the label index and the text after it
+fn match_label<'a>(line: &'a str, labels: &[&str]) -> Option<(usize, &'a str)> {
+    labels.iter().enumerate().find_map(|(idx, label)| {
+        let head = line.get(..label.len())?;
+        if !head.eq_ignore_ascii_case(label) {
+            return None;
+        }
+        let rest = line[label.len()..].trim_start();
+        if rest.is_empty() {
+            Some((idx, rest))
+        } else {
+            rest.strip_prefix(':').map(|value| (idx, value.trim()))
+        }
+    })
+}
+
+// Split a response into labeled sections such as `SUMMARY: ...`. Each section runs until the
+// next known label, so values may span several lines; bullet lists are joined with commas.
+// Keys are the labels as given; labels missing from the response are absent from the map.
+pub fn parse_labeled_sections(response: &str, labels: &[&str]) -> HashMap<String, String> {
+    let mut sections: Vec<(usize, Vec<(String, bool)>)> = Vec::new();
+
+    for line in response.lines() {
+        let (cleaned, is_bullet) = strip_markdown(line);
+        if cleaned.is_empty() {
+            continue;
+        }
+        match match_label(&cleaned, labels) {
+            Some((idx, rest)) => {
+                let mut lines = Vec::new();
+                if !rest.is_empty() {
+                    lines.push((rest.to_string(), false));
+                }
+                sections.push((idx, lines));
+            }
+            None => {
+                if let Some((_, lines)) = sections.last_mut() {
+                    lines.push((cleaned, is_bullet));
+                }
+            }
+        }
+    }
+
+    let mut parsed = HashMap::new();
+    for (idx, lines) in sections {
+        let all_bullets = lines.len() > 1 && lines.iter().filter(|(_, bullet)| *bullet).count() >= lines.len() - 1;
+        let separator = if all_bullets { ", " } else { " " };
+        let value = lines.into_iter().map(|(text, _)| text).collect::<Vec<_>>().join(separator);
+        if !value.is_empty() {
+            parsed.entry(labels[idx].to_string()).or_insert(value);
+        }
+    }
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_multi_line_markdown_sections() {
+        let response = "\
+**SUMMARY:** The council approved the new budget.
+It funds two schools
+and a library.
+
+**SENTIMENT:** POSITIVE
+**KEY_TOPICS:**
+- budget
+- schools
+**CATEGORY:** Politics";
+        let sections = parse_labeled_sections(response, &["SUMMARY", "SENTIMENT", "KEY_TOPICS", "CATEGORY", "LANGUAGE"]);
+
+        assert_eq!(sections["SUMMARY"], "The council approved the new budget. It funds two schools and a library.");
+        assert_eq!(sections["SENTIMENT"], "POSITIVE");
+        assert_eq!(sections["KEY_TOPICS"], "budget, schools");
+        assert_eq!(sections["CATEGORY"], "Politics");
+        assert!(!sections.contains_key("LANGUAGE"));
+    }
+}
@@ -0,0 +1,45 @@
+// pdf.rs - Text extraction for PDF documents, so they can be analyzed like HTML pages
+
+use url::Url;
+use crate::error::ScraperError;
+
+// First bytes of every PDF file
+const PDF_MAGIC: &[u8] = b"%PDF-";
+
+// Whether a response is a PDF: by Content-Type, by a .pdf URL path that isn't served as
+// HTML, or by the file signature
+pub fn is_pdf(content_type: &str, url: &str, bytes: &[u8]) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    if mime == "application/pdf" || bytes.starts_with(PDF_MAGIC) {
+        return true;
+    }
+    let is_pdf_path = Url::parse(url).is_ok_and(|url| url.path().to_ascii_lowercase().ends_with(".pdf"));
+    is_pdf_path && !matches!(mime.as_str(), "text/html" | "application/xhtml+xml")
+}
+
+// Plain text of a PDF, with blank-line runs collapsed. Parsing runs on the blocking pool
+// because large documents take a while.
+pub async fn extract_pdf_text(url: &str, bytes: Vec<u8>) -> Result<String, ScraperError> {
+    let pdf_error = |message: String| ScraperError::PdfExtract { url: url.to_string(), message };
+    let text = tokio::task::spawn_blocking(move || pdf_extract::extract_text_from_mem(&bytes))
+        .await
+        .map_err(|e| pdf_error(format!("PDF parser crashed: {}", e)))?
+        .map_err(|e| pdf_error(e.to_string()))?;
+
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    Ok(lines.join("\n").trim().to_string())
+}
+
+// Title for a PDF: the file name in its URL, or "Unknown"
+pub fn pdf_title(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.path_segments()?.rev().find(|segment| !segment.is_empty()).map(str::to_string))
+        .unwrap_or_else(|| "Unknown".to_string())
+}
@@ -0,0 +1,267 @@
+// pipeline.rs - Scrape one page and run the requested analyses on it
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use futures::future::join_all;
+use serde::Serialize;
+use tracing::{debug, info, warn};
+use crate::error::ScraperError;
+use crate::scrape::{FetchedPage, PageMetadata, ScrapedPage, Scraper};
+use crate::text::{detect_language, safe_prefix, url_slug};
+use crate::LLM_run::{ContentAnalysis, LLMRunner, SentimentResult, UNKNOWN_LANGUAGE};
+
+// Leading characters of the content sent for the snippet sentiment pass
+pub const SNIPPET_SENTIMENT_CHARS: usize = 500;
+
+// What scrape_and_analyze extracts and which analyses it runs
+#[derive(Debug, Clone)]
+pub struct ReportOptions {
+    // CSS selector list for the main content; empty to detect it automatically
+    pub selector: String,
+    // Text inside these selectors is dropped (scrape_and_analyze only; report_page and
+    // scrape_and_analyze_with use the scraper's own excludes)
+    pub excludes: Vec<String>,
+    // Most characters of content sent with each prompt (scrape_and_analyze only)
+    pub max_content_chars: Option<usize>,
+    // Translate the content into this language before analyzing it
+    pub translate_to: Option<String>,
+    // Run the full SUMMARY/SENTIMENT/TOPICS/CATEGORY analysis
+    pub analyze: bool,
+    // Re-classify into one of these categories after the analysis
+    pub categories: Vec<String>,
+    // Also run a separate sentiment analysis on the start of the content; off by default
+    // since the full analysis already reports the overall sentiment
+    pub snippet_sentiment: bool,
+    // Question answered from the content
+    pub question: Option<String>,
+    // Directory to save the text sent to the LLM in, one file per URL
+    pub save_content: Option<PathBuf>,
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        Self {
+            selector: String::new(),
+            excludes: Vec::new(),
+            max_content_chars: None,
+            translate_to: None,
+            analyze: true,
+            categories: Vec::new(),
+            snippet_sentiment: false,
+            question: None,
+            save_content: None,
+        }
+    }
+}
+
+// Write `content` to DIR/<url_slug>.txt, creating the directory if needed, and return the
+// file's path
+pub fn save_content(dir: &Path, url: &str, content: &str) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.txt", url_slug(url)));
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+// save_content for callers that carry on when it fails
+pub(crate) fn save_content_or_warn(dir: &Path, url: &str, content: &str) {
+    match save_content(dir, url, content) {
+        Ok(path) => debug!("Saved the content of {} to {}", url, path.display()),
+        Err(e) => warn!("Failed to save the content of {}: {}", url, e),
+    }
+}
+
+// A named part of a page that is analyzed on its own, e.g. "reviews=.reviews"
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub name: String,
+    pub selector: String,
+}
+
+impl Region {
+    // Parse a NAME=SELECTOR spec
+    pub fn parse(spec: &str) -> Result<Self, ScraperError> {
+        match spec.split_once('=') {
+            Some((name, selector)) if !name.trim().is_empty() && !selector.trim().is_empty() => Ok(Self {
+                name: name.trim().to_string(),
+                selector: selector.trim().to_string(),
+            }),
+            _ => Err(ScraperError::Config(format!("Expected a region as NAME=SELECTOR, got '{}'", spec))),
+        }
+    }
+}
+
+// Analysis of one region; `error` says why `analysis` is missing
+#[derive(Debug, Clone, Serialize)]
+pub struct RegionReport {
+    pub name: String,
+    pub selector: String,
+    pub analysis: Option<ContentAnalysis>,
+    pub error: Option<String>,
+}
+
+// Analyze each of `regions` of an already fetched page separately and concurrently, in the
+// order given. A region whose selector matches nothing gets an error rather than the whole page.
+pub async fn analyze_regions(
+    runner: &LLMRunner,
+    scraper: &Scraper,
+    page: &FetchedPage,
+    regions: &[Region],
+) -> Vec<RegionReport> {
+    join_all(regions.iter().map(|region| async move {
+        let result = match scraper.scrape_fetched_exact(page, &region.selector) {
+            Ok(scraped) => {
+                info!("Analyzing region '{}'...", region.name);
+                runner
+                    .analyze_web_content_with_context(
+                        &scraped.title,
+                        &scraped.combined_content(),
+                        &scraped.url,
+                        scraped.analysis_context(),
+                    )
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = &result {
+            warn!("Region '{}' failed: {}", region.name, e);
+        }
+        RegionReport {
+            name: region.name.clone(),
+            selector: region.selector.clone(),
+            error: result.as_ref().err().map(ToString::to_string),
+            analysis: result.ok(),
+        }
+    }))
+    .await
+}
+
+// Languages involved when the content was translated before analysis
+#[derive(Debug, Clone, Serialize)]
+pub struct Translation {
+    pub source_language: String,
+    pub target_language: String,
+}
+
+// Everything scrape_and_analyze found out about one page
+#[derive(Debug, Clone, Serialize)]
+pub struct PageReport {
+    // Final URL after redirects
+    pub url: String,
+    pub title: String,
+    pub metadata: PageMetadata,
+    // The text that was analyzed, translated when `translation` is set
+    pub content: String,
+    pub translation: Option<Translation>,
+    pub analysis: Option<ContentAnalysis>,
+    pub snippet_sentiment: Option<SentimentResult>,
+    pub answer: Option<String>,
+    // Analyses that failed; the others are still filled in
+    pub errors: Vec<String>,
+}
+
+// Scrape `url` and analyze it with a scraper and runner built from the defaults and the
+// environment, applying options.excludes and options.max_content_chars
+pub async fn scrape_and_analyze(url: &str, options: &ReportOptions) -> Result<PageReport, ScraperError> {
+    let excludes: Vec<&str> = options.excludes.iter().map(String::as_str).collect();
+    let scraper = Scraper::new()?.with_excludes(&excludes)?;
+    let mut runner = LLMRunner::from_env()?;
+    if let Some(max_chars) = options.max_content_chars {
+        runner = runner.with_max_content_chars(max_chars);
+    }
+    scrape_and_analyze_with(&runner, &scraper, url, options).await
+}
+
+// Same as scrape_and_analyze with a caller-configured scraper and runner
+pub async fn scrape_and_analyze_with(
+    runner: &LLMRunner,
+    scraper: &Scraper,
+    url: &str,
+    options: &ReportOptions,
+) -> Result<PageReport, ScraperError> {
+    let page = scraper.scrape(url, &options.selector).await?;
+    Ok(report_page(runner, &page, options).await)
+}
+
+// Run the analyses in `options` on a page that has already been scraped. A failed analysis
+// is recorded in PageReport::errors rather than failing the whole report.
+pub async fn report_page(runner: &LLMRunner, page: &ScrapedPage, options: &ReportOptions) -> PageReport {
+    let mut report = PageReport {
+        url: page.url.clone(),
+        title: page.title.clone(),
+        metadata: page.metadata.clone(),
+        content: page.combined_content(),
+        translation: None,
+        analysis: None,
+        snippet_sentiment: None,
+        answer: None,
+        errors: Vec::new(),
+    };
+    let fail = |report: &mut PageReport, what: &str, e: ScraperError| {
+        warn!("{} failed: {}", what, e);
+        report.errors.push(e.to_string());
+    };
+
+    if let Some(target_language) = options.translate_to.as_deref().map(str::trim).filter(|lang| !lang.is_empty()) {
+        let source_language = detect_language(&report.content).unwrap_or(UNKNOWN_LANGUAGE);
+        if source_language.eq_ignore_ascii_case(target_language) {
+            info!("Content is already in {}, skipping translation", target_language);
+        } else {
+            info!("Translating content from {} to {}...", source_language, target_language);
+            match runner.translate(&report.content, target_language).await {
+                Ok(translated) => {
+                    report.content = translated;
+                    report.translation = Some(Translation {
+                        source_language: source_language.to_string(),
+                        target_language: target_language.to_string(),
+                    });
+                }
+                Err(e) => fail(&mut report, "Translation (analyzing the original text)", e),
+            }
+        }
+    }
+
+    if let Some(dir) = &options.save_content {
+        save_content_or_warn(dir, &report.url, runner.truncate_content(&report.content));
+    }
+
+    if options.analyze {
+        info!("Requesting LLM analysis for the scraped content...");
+        match runner
+            .analyze_web_content_with_context(&report.title, &report.content, &report.url, page.analysis_context())
+            .await
+        {
+            Ok(analysis) => report.analysis = Some(analysis),
+            Err(e) => fail(&mut report, "LLM analysis", e),
+        }
+        let categories: Vec<&str> = options.categories.iter().map(String::as_str).collect();
+        if report.analysis.is_some() && !categories.is_empty() {
+            match runner.classify_content(&report.title, &report.content, &categories).await {
+                Ok(category) => {
+                    if let Some(analysis) = &mut report.analysis {
+                        analysis.category = category;
+                    }
+                }
+                Err(e) => fail(&mut report, "Classifying into the given categories", e),
+            }
+        }
+    }
+
+    if options.snippet_sentiment {
+        info!("Requesting specific sentiment analysis for a snippet...");
+        match runner.analyze_sentiment(safe_prefix(&report.content, SNIPPET_SENTIMENT_CHARS)).await {
+            Ok(sentiment) => report.snippet_sentiment = Some(sentiment),
+            Err(e) => fail(&mut report, "Snippet sentiment analysis", e),
+        }
+    }
+
+    if let Some(question) = options.question.as_deref().map(str::trim).filter(|question| !question.is_empty()) {
+        info!("Asking the LLM: {}", question);
+        match runner.answer_question(&report.content, question).await {
+            Ok(answer) => report.answer = Some(answer),
+            Err(e) => fail(&mut report, "Answering the question", e),
+        }
+    }
+    report
+}
@@ -0,0 +1,121 @@
+// robots.rs - Minimal robots.txt parser: Allow/Disallow rules and Crawl-delay for one user-agent
+
+use std::time::Duration;
+
+// Allow/Disallow rules that apply to one crawler
+#[derive(Debug, Clone, Default)]
+pub struct RobotsRules {
+    // (allow, path pattern) in file order
+    rules: Vec<(bool, String)>,
+    // Minimum time between requests asked for by the site
+    pub crawl_delay: Option<Duration>,
+}
+
+impl RobotsRules {
+    // Rules that allow everything, used when a site has no robots.txt
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    // Rules from the group matching `user_agent` most specifically, falling back to `*`
+    pub fn parse(robots_txt: &str, user_agent: &str) -> Self {
+        let user_agent = user_agent.to_lowercase();
+        let mut best: Option<(usize, RobotsRules)> = None;
+
+        let mut agents: Vec<String> = Vec::new();
+        let mut rules = RobotsRules::default();
+        let mut in_rules = false;
+        let mut finish_group = |agents: &[String], rules: &mut RobotsRules| {
+            let specificity = agents
+                .iter()
+                .filter_map(|agent| match agent.as_str() {
+                    "*" => Some(0),
+                    agent if user_agent.contains(agent) => Some(agent.len()),
+                    _ => None,
+                })
+                .max();
+            let group = std::mem::take(rules);
+            if let Some(specificity) = specificity
+                && best.as_ref().is_none_or(|(current, _)| specificity > *current)
+            {
+                best = Some((specificity, group));
+            }
+        };
+
+        for line in robots_txt.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match field.trim().to_lowercase().as_str() {
+                "user-agent" => {
+                    // A user-agent line after rules starts a new group
+                    if in_rules {
+                        finish_group(&agents, &mut rules);
+                        agents.clear();
+                        in_rules = false;
+                    }
+                    agents.push(value.to_lowercase());
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    // An empty Disallow means "allow everything" and adds no rule
+                    if !value.is_empty() {
+                        rules.rules.push((field.trim().eq_ignore_ascii_case("allow"), value.to_string()));
+                    }
+                }
+                "crawl-delay" => {
+                    in_rules = true;
+                    if let Ok(seconds) = value.parse::<f64>()
+                        && seconds.is_finite()
+                        && seconds >= 0.0
+                    {
+                        rules.crawl_delay = Some(Duration::from_secs_f64(seconds));
+                    }
+                }
+                _ => {}
+            }
+        }
+        finish_group(&agents, &mut rules);
+
+        best.map(|(_, rules)| rules).unwrap_or_default()
+    }
+
+    // Whether `path` (including any query string) may be fetched. The longest matching
+    // pattern decides; Allow wins a tie.
+    pub fn is_allowed(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| pattern_matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+}
+
+// Match a robots.txt path pattern, where `*` is any run of characters and a trailing `$`
+// anchors the end
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+
+    let mut pieces = pattern.split('*');
+    let first = pieces.next().unwrap_or("");
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let pieces: Vec<&str> = pieces.collect();
+    for (i, piece) in pieces.iter().enumerate() {
+        // The last piece of an anchored pattern has to sit at the very end
+        if anchored && i == pieces.len() - 1 {
+            return rest.ends_with(piece);
+        }
+        match rest.find(piece) {
+            Some(index) => rest = &rest[index + piece.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
@@ -0,0 +1,1437 @@
+// scrape.rs - Fetches pages and extracts text with CSS selectors
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, COOKIE, USER_AGENT};
+use reqwest::redirect::Policy;
+use reqwest::{Client, Proxy, RequestBuilder, Response};
+use tokio::time::{sleep, Instant};
+use tracing::{debug, instrument, warn};
+use ego_tree::iter::Edge;
+use ego_tree::NodeId;
+use encoding_rs::{Encoding, UTF_8};
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use crate::cache::DiskCache;
+use crate::clean::ContentCleaner;
+use crate::error::ScraperError;
+use crate::feed::{parse_feed, FeedEntry};
+use crate::markdown::render_markdown;
+use crate::pdf::{extract_pdf_text, is_pdf, pdf_title};
+use crate::robots::RobotsRules;
+use crate::sitemap::{decompress_sitemap, parse_sitemap, MAX_SITEMAPS};
+use crate::xpath::{select_xpaths, XPathMatch};
+
+// Separator placed between matched text blocks unless configured otherwise
+pub const DEFAULT_CONTENT_SEPARATOR: &str = "\n\n";
+
+// Elements whose start and end are line breaks when block boundaries are preserved
+const BLOCK_ELEMENTS: [&str; 27] = [
+    "address", "article", "aside", "blockquote", "br", "dd", "div", "dl", "dt", "fieldset", "figcaption",
+    "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "li", "main", "p", "pre",
+    "section",
+];
+
+// Below this many characters of body text, the meta description is sent along as context
+pub const SHORT_CONTENT_CHARS: usize = 500;
+
+// Fewest non-whitespace characters a scraped page needs before it is analyzed
+pub const DEFAULT_MIN_CONTENT_CHARS: usize = 20;
+
+// Below this many non-whitespace characters from the requested selectors, extraction is
+// retried with the broader FALLBACK_SELECTORS
+pub const DEFAULT_BROADEN_BELOW_CHARS: usize = 200;
+
+// Tried in order when the requested selectors match too little text
+pub const FALLBACK_SELECTORS: [&str; 3] = ["article", "main", "body"];
+
+// Blocks shorter than this are ignored when scoring readable content
+const MIN_READABLE_BLOCK_CHARS: usize = 25;
+
+// Descriptive <meta>/<link> tags from the page head
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PageMetadata {
+    pub description: Option<String>,
+    pub canonical_url: Option<String>,
+    pub og_title: Option<String>,
+    pub og_description: Option<String>,
+    pub og_image: Option<String>,
+}
+
+// Largest response body downloaded, unless configured otherwise
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 10 * 1024 * 1024;
+
+// Redirects followed before giving up, unless configured otherwise
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+// Raw HTML of a page and the URL it was finally served from after redirects
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchedPage {
+    pub final_url: String,
+    pub html: String,
+    // Text of a PDF document, in which case `html` is empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pdf_text: Option<String>,
+}
+
+// Title and text blocks pulled from a page
+#[derive(Debug, Clone)]
+pub struct ScrapedPage {
+    // Final URL after redirects
+    pub url: String,
+    pub title: String,
+    pub content_parts: Vec<String>,
+    // Selectors from the requested list that matched no elements
+    pub unmatched_selectors: Vec<String>,
+    // True when no selector matched and the content came from extract_readable
+    pub auto_extracted: bool,
+    // Fallback selector the content came from, when the requested ones matched too little
+    pub broadened_selector: Option<String>,
+    pub metadata: PageMetadata,
+    // Placed between content_parts by combined_content
+    pub separator: String,
+}
+
+impl ScrapedPage {
+    // All matched text blocks joined into one string for analysis
+    pub fn combined_content(&self) -> String {
+        self.content_parts.join(&self.separator)
+    }
+
+    // Extra context for the LLM: the meta (or Open Graph) description when the body is short
+    pub fn analysis_context(&self) -> Option<&str> {
+        let content_len: usize = self.content_parts.iter().map(|part| part.chars().count()).sum();
+        if content_len >= SHORT_CONTENT_CHARS {
+            return None;
+        }
+        self.metadata
+            .description
+            .as_deref()
+            .or(self.metadata.og_description.as_deref())
+    }
+}
+
+// Prefix scheme-less input with https://, except for paths to existing local files, which
+// become file:// URLs
+pub fn normalize_url(input: &str) -> String {
+    let input = input.trim();
+    if input.starts_with("http://") || input.starts_with("https://") || input.starts_with("file://") {
+        return input.to_string();
+    }
+    let path = Path::new(input);
+    if path.is_file()
+        && let Ok(path) = path.canonicalize()
+        && let Ok(url) = Url::from_file_path(path)
+    {
+        return url.to_string();
+    }
+    format!("https://{}", input)
+}
+
+// Local path a file:// URL points to
+fn local_file_path(url: &str) -> Option<PathBuf> {
+    if !url.starts_with("file://") {
+        return None;
+    }
+    Url::parse(url).ok()?.to_file_path().ok()
+}
+
+// Keep only links on the same host as `base_url`
+pub fn filter_same_domain(links: &[String], base_url: &str) -> Vec<String> {
+    let Some(host) = Url::parse(base_url).ok().and_then(|url| url.host_str().map(str::to_string)) else {
+        return Vec::new();
+    };
+    links
+        .iter()
+        .filter(|link| Url::parse(link).ok().and_then(|url| url.host_str().map(|h| h == host)).unwrap_or(false))
+        .cloned()
+        .collect()
+}
+
+fn non_whitespace_chars(parts: &[String]) -> usize {
+    parts.iter().flat_map(|part| part.chars()).filter(|c| !c.is_whitespace()).count()
+}
+
+// Browser-like user-agent sent unless overridden; some sites reject obvious bots outright
+pub const DEFAULT_USER_AGENT: &str =
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0 Safari/537.36";
+
+// HTTP fetching plus HTML title/content extraction
+pub struct Scraper {
+    client: Client,
+    // Sent with every request and matched against robots.txt groups
+    user_agent: String,
+    headers: HeaderMap,
+    // Proxy given with with_proxy; without one reqwest uses HTTP_PROXY/HTTPS_PROXY
+    proxy: Option<String>,
+    max_redirects: usize,
+    cache: Option<DiskCache>,
+    refresh: bool,
+    excludes: Vec<Selector>,
+    markdown: bool,
+    // Also take the parent's own text and neighbouring siblings of each matched element
+    surrounding_context: bool,
+    // Content selectors are XPath expressions instead of CSS
+    xpath: bool,
+    // Placed between matched text blocks
+    separator: String,
+    // Keep line breaks between the block elements of each match and skip matches nested
+    // in an earlier one
+    preserve_blocks: bool,
+    // Tidies each text block before analysis
+    cleaner: Option<ContentCleaner>,
+    // Only extract the section under the heading with this text
+    from_heading: Option<String>,
+    respect_robots: bool,
+    // Parsed robots.txt per origin, fetched once per run
+    robots: Mutex<HashMap<String, Arc<RobotsRules>>>,
+    delay: Duration,
+    // Pages with less non-whitespace text than this aren't worth analyzing
+    min_content_chars: usize,
+    // Selector matches with less text than this are retried with FALLBACK_SELECTORS
+    broaden_below_chars: usize,
+    // Downloads are abandoned once their body grows past this
+    max_response_bytes: usize,
+    // Earliest time the next request to each origin may start
+    next_fetch: Mutex<HashMap<String, Instant>>,
+}
+
+impl Scraper {
+    pub fn new() -> Result<Self, ScraperError> {
+        let client = build_client(None, DEFAULT_MAX_REDIRECTS)?;
+
+        Ok(Self {
+            client,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            headers: HeaderMap::new(),
+            proxy: None,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            cache: None,
+            refresh: false,
+            excludes: Vec::new(),
+            markdown: false,
+            surrounding_context: false,
+            xpath: false,
+            separator: DEFAULT_CONTENT_SEPARATOR.to_string(),
+            preserve_blocks: false,
+            cleaner: None,
+            from_heading: None,
+            respect_robots: false,
+            robots: Mutex::new(HashMap::new()),
+            delay: Duration::ZERO,
+            min_content_chars: DEFAULT_MIN_CONTENT_CHARS,
+            broaden_below_chars: DEFAULT_BROADEN_BELOW_CHARS,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            next_fetch: Mutex::new(HashMap::new()),
+        })
+    }
+
+    // Send `user_agent` instead of the default browser-like string
+    pub fn with_user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = user_agent.to_string();
+        self
+    }
+
+    // Extra headers sent with every request, e.g. ("Accept-Language", "en-US") or an
+    // Authorization header. Values may carry credentials, so they are marked sensitive and
+    // never show up in logs or errors.
+    pub fn with_headers(mut self, headers: Vec<(String, String)>) -> Result<Self, ScraperError> {
+        for (name, value) in headers {
+            let header_name = HeaderName::from_bytes(name.trim().as_bytes())
+                .map_err(|e| ScraperError::Config(format!("Invalid header name '{}': {}", name, e)))?;
+            let mut header_value = HeaderValue::from_str(value.trim())
+                .map_err(|e| ScraperError::Config(format!("Invalid value for header '{}': {}", name, e)))?;
+            header_value.set_sensitive(true);
+            self.headers.append(header_name, header_value);
+        }
+        Ok(self)
+    }
+
+    // Send `cookie` (e.g. "session=abc123; theme=dark", as copied from a logged-in browser)
+    // in the Cookie header of every request
+    pub fn with_cookie(self, cookie: &str) -> Result<Self, ScraperError> {
+        self.with_headers(vec![(COOKIE.to_string(), cookie.to_string())])
+    }
+
+    // Send every request through the proxy at `proxy_url` (e.g. "http://proxy.corp:3128").
+    // Without this the standard HTTP_PROXY/HTTPS_PROXY/ALL_PROXY variables are honored.
+    pub fn with_proxy(mut self, proxy_url: &str) -> Result<Self, ScraperError> {
+        self.client = build_client(Some(proxy_url), self.max_redirects)?;
+        self.proxy = Some(proxy_url.to_string());
+        Ok(self)
+    }
+
+    // Fail with ScraperError::Redirect after more than `max_redirects` redirects (0 rejects any)
+    pub fn with_max_redirects(mut self, max_redirects: usize) -> Result<Self, ScraperError> {
+        self.client = build_client(self.proxy.as_deref(), max_redirects)?;
+        self.max_redirects = max_redirects;
+        Ok(self)
+    }
+
+    // Drop text inside elements matching any of `selectors` (e.g. "nav", ".cookie-banner").
+    // Excludes apply within the matched content region, not to the page as a whole.
+    pub fn with_excludes(mut self, selectors: &[&str]) -> Result<Self, ScraperError> {
+        self.excludes = selectors
+            .iter()
+            .map(|selector_str| parse_selector(selector_str))
+            .collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+
+    // Keep fetched HTML under `dir` and reuse it for `ttl` instead of re-downloading
+    pub fn with_cache(mut self, dir: impl AsRef<Path>, ttl: Duration) -> Result<Self, ScraperError> {
+        self.cache = Some(DiskCache::new(dir, ttl)?);
+        Ok(self)
+    }
+
+    // Ignore cached pages and always re-download (the fresh copy is still cached)
+    pub fn refresh(mut self, refresh: bool) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    // Render matched content as Markdown instead of plain text, keeping headings, lists,
+    // links and code blocks
+    pub fn markdown(mut self, markdown: bool) -> Self {
+        self.markdown = markdown;
+        self
+    }
+
+    // Extend each selector match with its parent's own text and the sibling elements right
+    // before and after it, for pages where the prose is split across elements
+    pub fn surrounding_context(mut self, surrounding_context: bool) -> Self {
+        self.surrounding_context = surrounding_context;
+        self
+    }
+
+    // Treat content selectors as XPath 1.0 expressions (e.g. "//div[@id='main']/p[2]")
+    // rather than CSS selectors
+    pub fn xpath(mut self, xpath: bool) -> Self {
+        self.xpath = xpath;
+        self
+    }
+
+    // Join matched text blocks with `separator` instead of a blank line
+    pub fn with_separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+        self
+    }
+
+    // Separator placed between matched text blocks
+    pub fn separator(&self) -> &str {
+        &self.separator
+    }
+
+    // Keep paragraphs, headings and list items of a match on their own lines instead of
+    // running them together, and drop matches inside an earlier match, so the separator
+    // only falls between distinct matches
+    pub fn preserve_blocks(mut self, preserve_blocks: bool) -> Self {
+        self.preserve_blocks = preserve_blocks;
+        self
+    }
+
+    // Run every extracted text block through `cleaner` before it is analyzed
+    pub fn with_cleaner(mut self, cleaner: ContentCleaner) -> Self {
+        self.cleaner = Some(cleaner);
+        self
+    }
+
+    // Extract only the section that starts at the heading with this text (compared without
+    // case, an exact match preferred over a partial one) and runs to the next heading of the
+    // same or a higher level. Pages without such a heading are extracted as usual.
+    pub fn from_heading(mut self, heading: &str) -> Self {
+        let heading = heading.trim();
+        self.from_heading = (!heading.is_empty()).then(|| heading.to_string());
+        self
+    }
+
+    // Check robots.txt before downloading a page and refuse disallowed paths
+    pub fn respect_robots(mut self, respect_robots: bool) -> Self {
+        self.respect_robots = respect_robots;
+        self
+    }
+
+    // Wait at least `delay` between requests to the same host. With respect_robots, a longer
+    // Crawl-delay from robots.txt takes precedence.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    // Reject scraped pages with fewer than `min_chars` non-whitespace characters
+    pub fn with_min_content_chars(mut self, min_chars: usize) -> Self {
+        self.min_content_chars = min_chars;
+        self
+    }
+
+    // When the requested selectors match fewer than `min_chars` non-whitespace characters,
+    // take the content from the first of FALLBACK_SELECTORS that has enough (0 never broadens)
+    pub fn with_broaden_below(mut self, min_chars: usize) -> Self {
+        self.broaden_below_chars = min_chars;
+        self
+    }
+
+    // Abandon downloads whose body is larger than `max_bytes`
+    pub fn with_max_response_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_response_bytes = max_bytes;
+        self
+    }
+
+    // Download the raw HTML of a page, following redirects, or return it from the cache.
+    // PDFs are downloaded and their text extracted instead. Non-2xx responses and other
+    // content types are errors. file:// URLs are read from
+    // disk, bypassing the cache, robots.txt and the per-host delay.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn fetch(&self, url: &str) -> Result<FetchedPage, ScraperError> {
+        if let Some(path) = local_file_path(url) {
+            let bytes = tokio::fs::read(&path).await.map_err(|source| ScraperError::ReadFile {
+                path: path.display().to_string(),
+                source,
+            })?;
+            if is_pdf("", url, &bytes) {
+                let pdf_text = extract_pdf_text(url, bytes).await?;
+                return Ok(FetchedPage { final_url: url.to_string(), html: String::new(), pdf_text: Some(pdf_text) });
+            }
+            return Ok(FetchedPage { final_url: url.to_string(), html: decode_html(&bytes, ""), pdf_text: None });
+        }
+
+        let cache_key = self.page_cache_key(url);
+        if let Some(cache) = &self.cache
+            && !self.refresh
+            && let Some(cached) = cache.get(&cache_key)
+            && let Ok(page) = serde_json::from_str::<FetchedPage>(&cached)
+        {
+            debug!("Using cached page");
+            return Ok(page);
+        }
+
+        let response = self.send(url).await?;
+        let final_url = response.url().to_string();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        if !is_html_content_type(content_type) && !is_pdf(content_type, &final_url, &[]) {
+            return Err(ScraperError::UnsupportedContentType {
+                url: final_url,
+                content_type: content_type.to_string(),
+            });
+        }
+        let content_type = content_type.to_string();
+        let bytes = self.read_body(url, response).await?;
+        let page = if is_pdf(&content_type, &final_url, &bytes) {
+            let pdf_text = extract_pdf_text(&final_url, bytes).await?;
+            FetchedPage { final_url, html: String::new(), pdf_text: Some(pdf_text) }
+        } else {
+            FetchedPage { final_url, html: decode_html(&bytes, &content_type), pdf_text: None }
+        };
+
+        if let Some(cache) = &self.cache
+            && let Err(e) = serde_json::to_string(&page)
+                .map_err(std::io::Error::other)
+                .and_then(|cached| cache.put(&cache_key, &cached))
+        {
+            warn!("Failed to write page cache entry for {}: {}", url, e);
+        }
+        Ok(page)
+    }
+
+    // Cache key for `url` as this scraper requests it. The user agent and extra headers
+    // (cookies included) are part of it, so a page fetched while logged in is never served
+    // to a request without that login, or the other way round.
+    fn page_cache_key(&self, url: &str) -> String {
+        let mut headers: Vec<String> = self
+            .headers
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, String::from_utf8_lossy(value.as_bytes())))
+            .collect();
+        headers.sort();
+        let mut parts = vec![url, self.user_agent.as_str()];
+        parts.extend(headers.iter().map(String::as_str));
+        DiskCache::key(&parts)
+    }
+
+    // URLs listed in the sitemap at `url`, following <sitemapindex> entries to the sitemaps
+    // they list. Gzipped sitemaps (.xml.gz) are decompressed.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn fetch_sitemap(&self, url: &str) -> Result<Vec<String>, ScraperError> {
+        let mut pending = VecDeque::from([url.to_string()]);
+        let mut visited = HashSet::new();
+        let mut seen = HashSet::new();
+        let mut urls = Vec::new();
+        while let Some(sitemap_url) = pending.pop_front() {
+            if !visited.insert(sitemap_url.clone()) {
+                continue;
+            }
+            if visited.len() > MAX_SITEMAPS {
+                warn!("Stopping after {} sitemaps", MAX_SITEMAPS);
+                break;
+            }
+            let response = self.send(&sitemap_url).await?;
+            let bytes = self.read_body(&sitemap_url, response).await?;
+            let sitemap = parse_sitemap(&decompress_sitemap(&bytes)?);
+            debug!(
+                "Sitemap {} lists {} pages and {} sitemaps",
+                sitemap_url,
+                sitemap.urls.len(),
+                sitemap.sitemaps.len()
+            );
+            pending.extend(sitemap.sitemaps);
+            urls.extend(sitemap.urls.into_iter().filter(|url| seen.insert(url.clone())));
+        }
+        Ok(urls)
+    }
+
+    // Entries of the RSS or Atom feed at `url`
+    #[instrument(level = "debug", skip(self))]
+    pub async fn fetch_feed(&self, url: &str) -> Result<Vec<FeedEntry>, ScraperError> {
+        let response = self.send(url).await?;
+        let final_url = response.url().to_string();
+        let bytes = self.read_body(url, response).await?;
+        parse_feed(&bytes, &final_url)
+    }
+
+    // Send a GET for `url` after the robots.txt check and per-host delay, failing on
+    // non-2xx responses
+    async fn send(&self, url: &str) -> Result<Response, ScraperError> {
+        let parsed = Url::parse(url).ok();
+        let origin = parsed.as_ref().map(|parsed| parsed.origin().ascii_serialization());
+        let mut delay = self.delay;
+        if self.respect_robots
+            && let (Some(parsed), Some(origin)) = (&parsed, &origin)
+        {
+            let rules = self.robots_for(origin).await;
+            let mut path = parsed.path().to_string();
+            if let Some(query) = parsed.query() {
+                path = format!("{}?{}", path, query);
+            }
+            if !rules.is_allowed(&path) {
+                return Err(ScraperError::RobotsDisallowed { url: url.to_string() });
+            }
+            delay = delay.max(rules.crawl_delay.unwrap_or_default());
+        }
+        if let Some(origin) = &origin
+            && !delay.is_zero()
+        {
+            self.wait_for_host(origin, delay).await;
+        }
+
+        let response = self.get(url).send().await.map_err(|source| self.fetch_error(url, source))?;
+        if !response.status().is_success() {
+            return Err(ScraperError::HttpStatus {
+                url: response.url().to_string(),
+                status: response.status().as_u16(),
+            });
+        }
+        Ok(response)
+    }
+
+    // Download the (decompressed) body of `response`, failing with ResponseTooLarge as soon
+    // as it grows past max_response_bytes rather than buffering all of it
+    async fn read_body(&self, url: &str, mut response: Response) -> Result<Vec<u8>, ScraperError> {
+        let too_large = || ScraperError::ResponseTooLarge { url: url.to_string(), limit: self.max_response_bytes };
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|source| self.fetch_error(url, source))? {
+            if body.len() + chunk.len() > self.max_response_bytes {
+                return Err(too_large());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+
+    // Blame connection failures on the proxy when one is in use, so they aren't mistaken
+    // for the target site being down
+    fn fetch_error(&self, url: &str, source: reqwest::Error) -> ScraperError {
+        match self.proxy_for(url) {
+            _ if source.is_redirect() => ScraperError::Redirect { url: url.to_string(), source },
+            Some(proxy) if source.is_connect() => ScraperError::Proxy { proxy, url: url.to_string(), source },
+            _ => ScraperError::Fetch { url: url.to_string(), source },
+        }
+    }
+
+    // Proxy that requests to `url` go through: the explicit one, or the environment's unless
+    // NO_PROXY excludes the host, as reqwest does
+    fn proxy_for(&self, url: &str) -> Option<String> {
+        if self.proxy.is_some() {
+            return self.proxy.clone();
+        }
+        let no_proxy = ["NO_PROXY", "no_proxy"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.trim().is_empty());
+        if let Some(no_proxy) = no_proxy
+            && let Some(host) = Url::parse(url).ok().and_then(|url| url.host_str().map(str::to_string))
+            && no_proxy_matches(&no_proxy, &host)
+        {
+            return None;
+        }
+        let vars: &[&str] = if url.starts_with("https://") {
+            &["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]
+        } else {
+            &["HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"]
+        };
+        vars.iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.trim().is_empty())
+    }
+
+    // GET request carrying the configured user-agent and extra headers
+    fn get(&self, url: &str) -> RequestBuilder {
+        self.client
+            .get(url)
+            .header(USER_AGENT, &self.user_agent)
+            .headers(self.headers.clone())
+    }
+
+    // Reserve the next request slot for `origin` and sleep until it comes up, so concurrent
+    // fetches to one host are spaced `delay` apart
+    async fn wait_for_host(&self, origin: &str, delay: Duration) {
+        let start = {
+            let mut next_fetch = self.next_fetch.lock().unwrap();
+            let now = Instant::now();
+            let start = next_fetch.get(origin).map_or(now, |next| (*next).max(now));
+            next_fetch.insert(origin.to_string(), start + delay);
+            start
+        };
+        sleep(start.saturating_duration_since(Instant::now())).await;
+    }
+
+    // robots.txt rules for an origin, downloaded on first use. A robots.txt that is missing
+    // or can't be downloaded allows everything.
+    async fn robots_for(&self, origin: &str) -> Arc<RobotsRules> {
+        let cached = self.robots.lock().unwrap().get(origin).cloned();
+        if let Some(rules) = cached {
+            return rules;
+        }
+        let rules = Arc::new(self.fetch_robots(origin).await);
+        self.robots.lock().unwrap().insert(origin.to_string(), rules.clone());
+        rules
+    }
+
+    async fn fetch_robots(&self, origin: &str) -> RobotsRules {
+        let response = match self.get(&format!("{}/robots.txt", origin)).send().await {
+            Ok(response) if response.status().is_success() => response,
+            _ => return RobotsRules::allow_all(),
+        };
+        let url = response.url().to_string();
+        match self.read_body(&url, response).await {
+            Ok(bytes) => RobotsRules::parse(&String::from_utf8_lossy(&bytes), &self.user_agent),
+            Err(_) => RobotsRules::allow_all(),
+        }
+    }
+
+    // Fetch `url` and collect the text of every element matching `selector`. An empty
+    // selector, or one that matches nothing, falls back to extract_readable.
+    pub async fn scrape(&self, url: &str, selector: &str) -> Result<ScrapedPage, ScraperError> {
+        let page = self.fetch(url).await?;
+        self.scrape_fetched(page, selector)
+    }
+
+    // The extraction half of scrape, for a page that has already been fetched
+    pub fn scrape_fetched(&self, page: FetchedPage, selector: &str) -> Result<ScrapedPage, ScraperError> {
+        let FetchedPage { final_url, html, pdf_text } = page;
+        let mut page = match pdf_text {
+            // A PDF is one block of text, whatever the selector
+            Some(pdf_text) => ScrapedPage {
+                title: pdf_title(&final_url),
+                url: final_url,
+                content_parts: vec![pdf_text],
+                unmatched_selectors: Vec::new(),
+                auto_extracted: false,
+                broadened_selector: None,
+                metadata: PageMetadata::default(),
+                separator: self.separator.clone(),
+            },
+            None => {
+                let selectors = split_selector_list(selector);
+                let selectors: Vec<&str> = selectors.iter().map(String::as_str).collect();
+                let section = self.from_heading.as_deref().and_then(|heading| {
+                    let section = self.section_text(&html, heading);
+                    if section.is_none() {
+                        warn!("No heading '{}' on {}, extracting the whole page", heading, final_url);
+                    }
+                    section
+                });
+                let from_section = section.is_some();
+                let mut extraction = if let Some(section) = section {
+                    Extraction { parts: vec![section], unmatched_selectors: Vec::new() }
+                } else if self.xpath {
+                    self.extract_by_xpath(&html, &selectors)?
+                } else {
+                    self.extract_by_selectors(&html, &selectors)?
+                };
+                let auto_extracted = extraction.parts.is_empty();
+                if auto_extracted {
+                    let readable = self.extract_readable(&html);
+                    if readable.is_empty() {
+                        return Err(ScraperError::EmptyContent { selector: selector.to_string() });
+                    }
+                    extraction.parts.push(readable);
+                }
+                let mut broadened_selector = None;
+                if !auto_extracted
+                    && !from_section
+                    && non_whitespace_chars(&extraction.parts) < self.broaden_below_chars
+                    && let Some((fallback, parts)) = self.broader_extraction(&html)
+                {
+                    debug!("Selector '{}' matched too little text, using '{}'", selector, fallback);
+                    extraction.parts = parts;
+                    broadened_selector = Some(fallback.to_string());
+                }
+                ScrapedPage {
+                    url: final_url,
+                    title: Self::parse_title(&html),
+                    content_parts: extraction.parts,
+                    unmatched_selectors: extraction.unmatched_selectors,
+                    auto_extracted,
+                    broadened_selector,
+                    metadata: Self::extract_metadata(&html),
+                    separator: self.separator.clone(),
+                }
+            }
+        };
+        page.content_parts = self.clean_parts(page.content_parts);
+        self.ensure_enough_content(&page)?;
+        Ok(page)
+    }
+
+    // Like scrape_fetched, but the content only ever comes from what `selector` matches:
+    // no automatic extraction, broadening or --from-heading section, and EmptyContent when
+    // it matches nothing. Used to analyze separate regions of one page.
+    pub fn scrape_fetched_exact(&self, page: &FetchedPage, selector: &str) -> Result<ScrapedPage, ScraperError> {
+        let selectors = split_selector_list(selector);
+        let selectors: Vec<&str> = selectors.iter().map(String::as_str).collect();
+        let extraction = if self.xpath {
+            self.extract_by_xpath(&page.html, &selectors)?
+        } else {
+            self.extract_by_selectors(&page.html, &selectors)?
+        };
+        let content_parts = self.clean_parts(extraction.parts);
+        if content_parts.is_empty() {
+            return Err(ScraperError::EmptyContent { selector: selector.to_string() });
+        }
+        let page = ScrapedPage {
+            url: page.final_url.clone(),
+            title: Self::parse_title(&page.html),
+            content_parts,
+            unmatched_selectors: extraction.unmatched_selectors,
+            auto_extracted: false,
+            broadened_selector: None,
+            metadata: Self::extract_metadata(&page.html),
+            separator: self.separator.clone(),
+        };
+        self.ensure_enough_content(&page)?;
+        Ok(page)
+    }
+
+    // `parts` run through the cleaner, if one is set, without the ones left empty
+    fn clean_parts(&self, parts: Vec<String>) -> Vec<String> {
+        match &self.cleaner {
+            Some(cleaner) => parts.iter().map(|part| cleaner.clean(part)).filter(|part| !part.is_empty()).collect(),
+            None => parts,
+        }
+    }
+
+    // Fail with InsufficientContent when the page has fewer non-whitespace characters than
+    // the configured minimum, so no LLM call is spent on it
+    pub fn ensure_enough_content(&self, page: &ScrapedPage) -> Result<(), ScraperError> {
+        let chars = non_whitespace_chars(&page.content_parts);
+        if chars < self.min_content_chars {
+            return Err(ScraperError::InsufficientContent {
+                url: page.url.clone(),
+                chars,
+                min_chars: self.min_content_chars,
+            });
+        }
+        Ok(())
+    }
+
+    // Content of the first FALLBACK_SELECTORS entry with at least broaden_below_chars of text
+    fn broader_extraction(&self, html: &str) -> Option<(&'static str, Vec<String>)> {
+        FALLBACK_SELECTORS.into_iter().find_map(|selector| {
+            let parts = self.extract_by_selectors(html, &[selector]).ok()?.parts;
+            (non_whitespace_chars(&parts) >= self.broaden_below_chars).then_some((selector, parts))
+        })
+    }
+
+    // Text of the <title> element, or "Unknown"
+    pub fn parse_title(html: &str) -> String {
+        let document = Html::parse_document(html);
+        let title_selector = Selector::parse("title").unwrap();
+        document
+            .select(&title_selector)
+            .next()
+            .map(|element| element.text().collect::<String>())
+            .unwrap_or_else(|| "Unknown".to_string())
+    }
+
+    // Meta description, canonical URL and Open Graph title/description/image
+    pub fn extract_metadata(html: &str) -> PageMetadata {
+        let document = Html::parse_document(html);
+        let content_of = |selector_str: &str, attr: &str| {
+            let selector = Selector::parse(selector_str).unwrap();
+            document
+                .select(&selector)
+                .filter_map(|element| element.value().attr(attr))
+                .map(str::trim)
+                .find(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+
+        PageMetadata {
+            description: content_of(r#"meta[name="description" i]"#, "content"),
+            canonical_url: content_of(r#"link[rel="canonical" i]"#, "href"),
+            og_title: content_of(r#"meta[property="og:title"]"#, "content"),
+            og_description: content_of(r#"meta[property="og:description"]"#, "content"),
+            og_image: content_of(r#"meta[property="og:image"]"#, "content"),
+        }
+    }
+
+    // Absolute http(s) targets of every <a href> on the page, resolved against `base_url`,
+    // without fragments and de-duplicated in first-seen order
+    pub fn extract_links(html: &str, base_url: &str) -> Vec<String> {
+        let document = Html::parse_document(html);
+        let link_selector = Selector::parse("a[href]").unwrap();
+        let base = Url::parse(base_url).ok();
+
+        let mut seen = HashSet::new();
+        let mut links = Vec::new();
+        for element in document.select(&link_selector) {
+            let Some(href) = element.value().attr("href") else {
+                continue;
+            };
+            let resolved = match &base {
+                Some(base) => base.join(href.trim()),
+                None => Url::parse(href.trim()),
+            };
+            let Ok(mut link) = resolved else {
+                continue;
+            };
+            if !matches!(link.scheme(), "http" | "https") {
+                continue;
+            }
+            link.set_fragment(None);
+            if seen.insert(link.to_string()) {
+                links.push(link.to_string());
+            }
+        }
+        links
+    }
+
+    // Non-empty text of every element matching `selector_str`, which may list several
+    // selectors separated by commas or semicolons
+    pub fn extract_by_selector(&self, html: &str, selector_str: &str) -> Result<Vec<String>, ScraperError> {
+        let selectors = split_selector_list(selector_str);
+        let selectors: Vec<&str> = selectors.iter().map(String::as_str).collect();
+        Ok(self.extract_by_selectors(html, &selectors)?.parts)
+    }
+
+    // Same as extract_by_selector, keeping each match separate with the element it came from
+    pub fn extract_by_selector_detailed(
+        &self,
+        html: &str,
+        selector_str: &str,
+    ) -> Result<Vec<ExtractedBlock>, ScraperError> {
+        let selectors = split_selector_list(selector_str);
+        let selectors: Vec<&str> = selectors.iter().map(String::as_str).collect();
+        Ok(self.select_blocks(html, &selectors)?.0)
+    }
+
+    // Text of every element matching any of `selectors`, in document order
+    pub fn extract_by_selectors(&self, html: &str, selectors: &[&str]) -> Result<Extraction, ScraperError> {
+        let (blocks, unmatched_selectors) = self.select_blocks(html, selectors)?;
+        Ok(Extraction {
+            parts: blocks.into_iter().map(|block| block.text).collect(),
+            unmatched_selectors,
+        })
+    }
+
+    // Non-empty blocks matched by `selectors`, plus the selectors that matched nothing
+    fn select_blocks(&self, html: &str, selectors: &[&str]) -> Result<(Vec<ExtractedBlock>, Vec<String>), ScraperError> {
+        let parsed = selectors
+            .iter()
+            .map(|selector_str| parse_selector(selector_str))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let document = Html::parse_document(html);
+        let mut match_counts = vec![0usize; parsed.len()];
+        let mut blocks = Vec::new();
+        // Nodes already taken as context, so neighbouring matches don't repeat each other
+        let mut taken = HashSet::new();
+        let mut matched_ids = HashSet::new();
+        for element in document.root_element().descendants().filter_map(ElementRef::wrap) {
+            let mut matched = false;
+            for (selector, count) in parsed.iter().zip(match_counts.iter_mut()) {
+                if selector.matches(&element) {
+                    *count += 1;
+                    matched = true;
+                }
+            }
+            if !matched || (self.preserve_blocks && inside_any(element, &matched_ids)) {
+                continue;
+            }
+            let index = matched_ids.len();
+            matched_ids.insert(element.id());
+            let text = if self.surrounding_context {
+                self.context_text(element, &mut taken)
+            } else {
+                self.element_text(element)
+            };
+            if !text.is_empty() {
+                blocks.push(ExtractedBlock {
+                    text,
+                    tag_name: element.value().name().to_string(),
+                    classes: element.value().classes().map(str::to_string).collect(),
+                    index,
+                });
+            }
+        }
+
+        let unmatched_selectors = selectors
+            .iter()
+            .zip(&match_counts)
+            .filter(|(_, count)| **count == 0)
+            .map(|(selector, _)| selector.to_string())
+            .collect();
+
+        Ok((blocks, unmatched_selectors))
+    }
+
+    // Same as extract_by_selectors for XPath expressions. Matched elements give their text
+    // as a CSS match would; matched text and attribute nodes give their value.
+    pub fn extract_by_xpath(&self, html: &str, expressions: &[&str]) -> Result<Extraction, ScraperError> {
+        let document = Html::parse_document(html);
+        let matches = select_xpaths(&document, expressions)?;
+
+        let mut parts = Vec::new();
+        let mut taken = HashSet::new();
+        let mut matched_ids = HashSet::new();
+        let mut unmatched_selectors = Vec::new();
+        for (expression, matches) in expressions.iter().zip(matches) {
+            if matches.is_empty() {
+                unmatched_selectors.push(expression.to_string());
+            }
+            for found in matches {
+                let text = match found {
+                    XPathMatch::Element(id) => {
+                        let Some(element) = document.tree.get(id).and_then(ElementRef::wrap) else {
+                            continue;
+                        };
+                        if self.preserve_blocks && inside_any(element, &matched_ids) {
+                            continue;
+                        }
+                        matched_ids.insert(element.id());
+                        if self.surrounding_context {
+                            self.context_text(element, &mut taken)
+                        } else {
+                            self.element_text(element)
+                        }
+                    }
+                    XPathMatch::Text(text) => text.split_whitespace().collect::<Vec<_>>().join(" "),
+                };
+                if !text.is_empty() {
+                    parts.push(text);
+                }
+            }
+        }
+        Ok(Extraction { parts, unmatched_selectors })
+    }
+
+    // Count the elements each of `selectors` matches in `html`, with the text of the first
+    // match, to help pick a content selector
+    pub fn test_selectors(&self, html: &str, selectors: &[&str]) -> Result<Vec<SelectorMatch>, ScraperError> {
+        let document = Html::parse_document(html);
+        selectors
+            .iter()
+            .map(|selector_str| {
+                let selector = parse_selector(selector_str)?;
+                let mut matched = document.select(&selector);
+                let first_text = matched.next().map(|element| self.element_text(element));
+                Ok(SelectorMatch {
+                    selector: selector_str.to_string(),
+                    matches: first_text.as_ref().map_or(0, |_| 1 + matched.count()),
+                    first_text,
+                })
+            })
+            .collect()
+    }
+
+    // Main content of the page, found with a simple readability heuristic: every paragraph-like
+    // block scores by its length and commas, the score is credited to its parent (and half to
+    // its grandparent), and the container with the best score after discounting link-heavy
+    // text wins. Falls back to the whole <body> when no block qualifies.
+    pub fn extract_readable(&self, html: &str) -> String {
+        let document = Html::parse_document(html);
+        let block_selector = Selector::parse("p, pre, td, blockquote").unwrap();
+
+        let mut scores: HashMap<NodeId, f64> = HashMap::new();
+        for block in document.select(&block_selector) {
+            let text = block.text().collect::<String>();
+            let text_len = text.trim().chars().count();
+            if text_len < MIN_READABLE_BLOCK_CHARS {
+                continue;
+            }
+            let score = 1.0 + text.matches(',').count() as f64 + (text_len as f64 / 100.0).min(3.0);
+
+            let mut ancestors = block.ancestors().filter_map(ElementRef::wrap);
+            if let Some(parent) = ancestors.next() {
+                *scores.entry(parent.id()).or_default() += score;
+                if let Some(grandparent) = ancestors.next() {
+                    *scores.entry(grandparent.id()).or_default() += score / 2.0;
+                }
+            }
+        }
+
+        let best = scores
+            .into_iter()
+            .filter_map(|(id, score)| {
+                let element = document.tree.get(id).and_then(ElementRef::wrap)?;
+                Some((element, score * (1.0 - link_density(element))))
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(element, _)| element);
+
+        let body_selector = Selector::parse("body").unwrap();
+        match best.or_else(|| document.select(&body_selector).next()) {
+            Some(element) => self.element_text(element),
+            None => String::new(),
+        }
+    }
+
+    // Text of the section under the heading matching `heading`, see from_heading
+    fn section_text(&self, html: &str, heading: &str) -> Option<String> {
+        let document = Html::parse_document(html);
+        let heading_selector = Selector::parse("h1, h2, h3, h4, h5, h6").unwrap();
+        let wanted = heading.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        let headings: Vec<(ElementRef<'_>, String)> = document
+            .select(&heading_selector)
+            .map(|element| {
+                let text = element.text().flat_map(str::split_whitespace).collect::<Vec<_>>().join(" ");
+                (element, text.to_lowercase())
+            })
+            .collect();
+        let start = headings
+            .iter()
+            .find(|(_, text)| *text == wanted)
+            .or_else(|| headings.iter().find(|(_, text)| text.contains(&wanted)))
+            .map(|(element, _)| *element)?;
+        let level = heading_level(start.value().name())?;
+
+        let excluded: HashSet<NodeId> = self
+            .excludes
+            .iter()
+            .flat_map(|selector| document.select(selector))
+            .map(|element| element.id())
+            .collect();
+        let mut raw = String::new();
+        for node in document.tree.root().descendants().skip_while(|node| node.id() != start.id()) {
+            if let Node::Element(element) = node.value() {
+                if node.id() != start.id() && heading_level(element.name()).is_some_and(|other| other <= level) {
+                    break;
+                }
+                if BLOCK_ELEMENTS.contains(&element.name()) || heading_level(element.name()).is_some() {
+                    raw.push('\n');
+                }
+            }
+            if let Node::Text(text) = node.value()
+                && !node.ancestors().any(|ancestor| {
+                    excluded.contains(&ancestor.id())
+                        || ancestor.value().as_element().is_some_and(|element| NON_CONTENT_ELEMENTS.contains(&element.name()))
+                })
+            {
+                for word in text.split_whitespace() {
+                    raw.push(' ');
+                    raw.push_str(word);
+                }
+            }
+        }
+
+        let lines: Vec<&str> = raw.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+        let text = if self.preserve_blocks { lines.join("\n") } else { lines.join(" ") };
+        (!text.is_empty()).then_some(text)
+    }
+
+    // Text of an HTML fragment such as a feed entry's content, with the same excludes and
+    // Markdown rendering as matched elements
+    pub fn html_text(&self, html: &str) -> String {
+        let fragment = Html::parse_fragment(html);
+        let text = self.element_text(fragment.root_element());
+        match &self.cleaner {
+            Some(cleaner) => cleaner.clean(&text),
+            None => text,
+        }
+    }
+
+    // The parent's own text, the previous sibling element, `element` and the next sibling
+    // element, skipping any node in `taken`
+    fn context_text(&self, element: ElementRef<'_>, taken: &mut HashSet<NodeId>) -> String {
+        let mut pieces = Vec::new();
+        if let Some(parent) = element.parent().and_then(ElementRef::wrap)
+            && taken.insert(parent.id())
+        {
+            let own_text = parent
+                .children()
+                .filter_map(|child| child.value().as_text())
+                .map(|text| text.trim())
+                .filter(|text| !text.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !own_text.is_empty() {
+                pieces.push(own_text);
+            }
+        }
+
+        let previous = element.prev_siblings().find_map(ElementRef::wrap);
+        let next = element.next_siblings().find_map(ElementRef::wrap);
+        for node in [previous, Some(element), next].into_iter().flatten() {
+            if taken.insert(node.id()) {
+                let text = self.element_text(node);
+                if !text.is_empty() {
+                    pieces.push(text);
+                }
+            }
+        }
+        pieces.join(" ")
+    }
+
+    // Descendant text (or Markdown) of `element`, skipping anything under an excluded element
+    fn element_text(&self, element: ElementRef<'_>) -> String {
+        if self.excludes.is_empty() && !self.markdown && !self.preserve_blocks {
+            return element.text().collect::<Vec<_>>().join(" ").trim().to_string();
+        }
+
+        let excluded: HashSet<NodeId> = self
+            .excludes
+            .iter()
+            .flat_map(|selector| element.select(selector))
+            .map(|excluded_element| excluded_element.id())
+            .collect();
+        if self.markdown {
+            return render_markdown(element, &excluded);
+        }
+        if self.preserve_blocks {
+            return block_text(element, &excluded);
+        }
+
+        element
+            .descendants()
+            .filter_map(|node| node.value().as_text().map(|text| (node, text)))
+            .filter(|(node, _)| !node.ancestors().any(|ancestor| excluded.contains(&ancestor.id())))
+            .map(|(_, text)| &**text)
+            .collect::<Vec<_>>()
+            .join(" ")
+            .trim()
+            .to_string()
+    }
+}
+
+// How far into the document to look for a <meta charset> declaration
+const CHARSET_SNIFF_BYTES: usize = 1024;
+
+// Decode a response body using the charset from the Content-Type header, then the page's
+// <meta charset>, then a byte-order mark, falling back to UTF-8
+pub fn decode_html(bytes: &[u8], content_type: &str) -> String {
+    let encoding = charset_param(content_type)
+        .or_else(|| meta_charset(&bytes[..bytes.len().min(CHARSET_SNIFF_BYTES)]))
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+        .unwrap_or(UTF_8);
+    // decode() still lets a BOM override the declared encoding, as browsers do
+    let (html, _, _) = encoding.decode(bytes);
+    html.into_owned()
+}
+
+// Whether a NO_PROXY list such as "localhost,.corp.example,10.0.0.0/8" covers `host`. Domains
+// match themselves and their subdomains, IPs match exactly or by CIDR range and "*" matches
+// everything.
+fn no_proxy_matches(no_proxy: &str, host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let host_ip = host.parse::<IpAddr>().ok();
+    no_proxy.split(',').map(str::trim).filter(|entry| !entry.is_empty()).any(|entry| {
+        if entry == "*" {
+            return true;
+        }
+        if let Some(host_ip) = host_ip {
+            return match entry.split_once('/') {
+                Some((network, prefix)) => match (network.parse::<IpAddr>(), prefix.parse::<u32>()) {
+                    (Ok(network), Ok(prefix)) => in_network(host_ip, network, prefix),
+                    _ => false,
+                },
+                None => entry.parse::<IpAddr>().is_ok_and(|ip| ip == host_ip),
+            };
+        }
+        let domain = entry.trim_start_matches('.');
+        host.eq_ignore_ascii_case(domain)
+            || host.len() > domain.len()
+                && host[host.len() - domain.len()..].eq_ignore_ascii_case(domain)
+                && host.as_bytes()[host.len() - domain.len() - 1] == b'.'
+    })
+}
+
+// Whether `ip` falls in the `network`/`prefix` CIDR range
+fn in_network(ip: IpAddr, network: IpAddr, prefix: u32) -> bool {
+    let (ip, network, bits) = match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => (u32::from(ip) as u128, u32::from(network) as u128, 32),
+        (IpAddr::V6(ip), IpAddr::V6(network)) => (u128::from(ip), u128::from(network), 128),
+        _ => return false,
+    };
+    if prefix > bits {
+        return false;
+    }
+    let mask = if prefix == 0 { 0 } else { (u128::MAX << (bits - prefix)) & (u128::MAX >> (128 - bits)) };
+    ip & mask == network & mask
+}
+
+// `charset` parameter of a Content-Type value such as "text/html; charset=windows-1251"
+fn charset_param(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+    })
+}
+
+// Charset declared by `<meta charset="...">` or `<meta http-equiv="Content-Type" content="...; charset=...">`
+fn meta_charset(head: &[u8]) -> Option<String> {
+    let head = String::from_utf8_lossy(head).to_ascii_lowercase();
+    let mut search_from = 0;
+    while let Some(offset) = head[search_from..].find("charset=") {
+        let index = search_from + offset;
+        search_from = index + "charset=".len();
+
+        let tag_start = head[..index].rfind('<');
+        let inside_meta = tag_start.is_some_and(|start| head[start..].starts_with("<meta") && !head[start..index].contains('>'));
+        if !inside_meta {
+            continue;
+        }
+        let value: String = head[search_from..]
+            .trim_start_matches(['"', '\''])
+            .chars()
+            .take_while(|c| !matches!(c, '"' | '\'' | ';' | '>' | '/') && !c.is_whitespace())
+            .collect();
+        if !value.is_empty() {
+            return Some(value);
+        }
+    }
+    None
+}
+
+// Whether a Content-Type header describes an HTML document. A missing header is given
+// the benefit of the doubt.
+fn is_html_content_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    matches!(mime.as_str(), "" | "text/html" | "application/xhtml+xml")
+}
+
+// HTTP client that follows at most `max_redirects` redirects and errors on redirect loops.
+// It advertises gzip, brotli and deflate in Accept-Encoding and decodes compressed bodies, so
+// servers that only send compressed pages still yield HTML.
+fn build_client(proxy_url: Option<&str>, max_redirects: usize) -> Result<Client, ScraperError> {
+    let redirect_policy = Policy::custom(move |attempt| {
+        if attempt.previous().contains(attempt.url()) {
+            attempt.error("redirect loop detected")
+        } else if attempt.previous().len() > max_redirects {
+            attempt.error(format!("more than {} redirects", max_redirects))
+        } else {
+            attempt.follow()
+        }
+    });
+
+    let mut builder = Client::builder()
+        .redirect(redirect_policy)
+        .gzip(true)
+        .brotli(true)
+        .deflate(true);
+    if let Some(proxy_url) = proxy_url {
+        let proxy = Proxy::all(proxy_url)
+            .map_err(|e| ScraperError::Config(format!("Invalid proxy URL '{}': {}", proxy_url, e)))?;
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder.build()?)
+}
+
+// Elements whose text is never page content
+const NON_CONTENT_ELEMENTS: [&str; 4] = ["script", "style", "noscript", "template"];
+
+// 1 to 6 for the heading elements h1 to h6
+fn heading_level(name: &str) -> Option<u8> {
+    match name.as_bytes() {
+        [b'h', level @ b'1'..=b'6'] => Some(level - b'0'),
+        _ => None,
+    }
+}
+
+// Whether `element` lies inside one of the elements in `ids`
+fn inside_any(element: ElementRef<'_>, ids: &HashSet<NodeId>) -> bool {
+    element.ancestors().any(|ancestor| ids.contains(&ancestor.id()))
+}
+
+// Text of `element` with a line break at the start and end of every block element, one
+// line per block with its whitespace collapsed, skipping anything under `excluded`
+fn block_text(element: ElementRef<'_>, excluded: &HashSet<NodeId>) -> String {
+    let mut raw = String::new();
+    let mut skip_depth = 0usize;
+    for edge in element.traverse() {
+        match edge {
+            Edge::Open(node) => {
+                if skip_depth > 0 || excluded.contains(&node.id()) {
+                    skip_depth += 1;
+                    continue;
+                }
+                match node.value() {
+                    Node::Text(text) => {
+                        for word in text.split_whitespace() {
+                            raw.push(' ');
+                            raw.push_str(word);
+                        }
+                    }
+                    Node::Element(tag) if BLOCK_ELEMENTS.contains(&tag.name()) => raw.push('\n'),
+                    _ => {}
+                }
+            }
+            Edge::Close(node) => {
+                if skip_depth > 0 {
+                    skip_depth -= 1;
+                } else if let Node::Element(tag) = node.value()
+                    && BLOCK_ELEMENTS.contains(&tag.name())
+                {
+                    raw.push('\n');
+                }
+            }
+        }
+    }
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Share of an element's text that sits inside links
+fn link_density(element: ElementRef<'_>) -> f64 {
+    let link_selector = Selector::parse("a").unwrap();
+    let text_len = element.text().map(|text| text.chars().count()).sum::<usize>();
+    if text_len == 0 {
+        return 1.0;
+    }
+    let link_len = element
+        .select(&link_selector)
+        .flat_map(|link| link.text())
+        .map(|text| text.chars().count())
+        .sum::<usize>();
+    link_len as f64 / text_len as f64
+}
+
+// Parse one CSS selector, mapping failures to SelectorParse
+fn parse_selector(selector_str: &str) -> Result<Selector, ScraperError> {
+    Selector::parse(selector_str).map_err(|e| ScraperError::SelectorParse {
+        selector: selector_str.to_string(),
+        message: format!("{:?}", e),
+    })
+}
+
+// How one candidate selector fares on a page, from Scraper::test_selectors
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectorMatch {
+    pub selector: String,
+    pub matches: usize,
+    // Text of the first matching element (after excludes), if any
+    pub first_text: Option<String>,
+}
+
+// Text of one matched element, with what a caller needs to route it
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtractedBlock {
+    pub text: String,
+    pub tag_name: String,
+    pub classes: Vec<String>,
+    // Position of the element among all matches in document order, counting matches
+    // that had no text
+    pub index: usize,
+}
+
+// Text blocks matched by a selector list, plus the selectors that matched nothing
+#[derive(Debug, Clone, Default)]
+pub struct Extraction {
+    pub parts: Vec<String>,
+    pub unmatched_selectors: Vec<String>,
+}
+
+// Split "article, .note; #main" into individual selectors, ignoring separators inside
+// brackets, parentheses and quotes (e.g. `a[title="x,y"]` or `:is(h1, h2)`)
+pub fn split_selector_list(selector_str: &str) -> Vec<String> {
+    let mut selectors = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+
+    for c in selector_str.chars() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[') => depth += 1,
+            (None, ')' | ']') => depth = depth.saturating_sub(1),
+            (None, ',' | ';') if depth == 0 => {
+                selectors.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    selectors.push(current);
+
+    selectors
+        .into_iter()
+        .map(|selector| selector.trim().to_string())
+        .filter(|selector| !selector.is_empty())
+        .collect()
+}
+
+// Fetch `url` and collect the text of every element matching `selector`
+pub async fn scrape_content(url: &str, selector: &str) -> Result<ScrapedPage, ScraperError> {
+    Scraper::new()?.scrape(url, selector).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "Café" in ISO-8859-1: é is the single byte 0xE9, which is invalid as UTF-8
+    const LATIN1_BODY: &[u8] = b"<p>Caf\xe9</p>";
+
+    #[test]
+    fn decodes_latin1_from_the_content_type_header() {
+        let html = decode_html(LATIN1_BODY, "text/html; charset=iso-8859-1");
+        assert_eq!(html, "<p>Café</p>");
+    }
+
+    #[test]
+    fn decodes_latin1_from_meta_charset() {
+        let mut page = b"<html><head><meta charset=\"iso-8859-1\"></head><body>".to_vec();
+        page.extend_from_slice(LATIN1_BODY);
+        assert!(decode_html(&page, "text/html").ends_with("<p>Café</p>"));
+    }
+
+    #[test]
+    fn no_proxy_lists_match_like_reqwest() {
+        let no_proxy = "localhost, .corp.example,intranet.example,10.0.0.0/8,::1";
+        assert!(no_proxy_matches(no_proxy, "localhost"));
+        assert!(no_proxy_matches(no_proxy, "wiki.corp.example"));
+        assert!(no_proxy_matches(no_proxy, "corp.example"));
+        assert!(no_proxy_matches(no_proxy, "docs.intranet.example"));
+        assert!(no_proxy_matches(no_proxy, "10.1.2.3"));
+        assert!(no_proxy_matches(no_proxy, "[::1]"));
+        assert!(!no_proxy_matches(no_proxy, "notintranet.example"));
+        assert!(!no_proxy_matches(no_proxy, "example.com"));
+        assert!(!no_proxy_matches(no_proxy, "11.0.0.1"));
+        assert!(no_proxy_matches("*", "example.com"));
+    }
+}
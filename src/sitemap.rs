@@ -0,0 +1,49 @@
+// sitemap.rs - Parses sitemap.xml files (<urlset> and <sitemapindex>) into URL lists
+
+use std::io::Read;
+use flate2::read::GzDecoder;
+use scraper::{Html, Selector};
+use crate::error::ScraperError;
+
+// Most sitemap files fetch_sitemap downloads when following a sitemap index
+pub const MAX_SITEMAPS: usize = 1000;
+
+// First bytes of a gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+// Contents of one sitemap file
+#[derive(Debug, Clone, Default)]
+pub struct Sitemap {
+    // Page URLs from <urlset>/<url>/<loc>
+    pub urls: Vec<String>,
+    // Nested sitemap URLs from <sitemapindex>/<sitemap>/<loc>
+    pub sitemaps: Vec<String>,
+}
+
+// Collect the http(s) <loc> entries of a sitemap or sitemap index
+pub fn parse_sitemap(xml: &str) -> Sitemap {
+    // html5ever copes with the XML well enough for these flat documents and decodes entities
+    let document = Html::parse_document(xml);
+    let locs = |selector_str: &str| {
+        let selector = Selector::parse(selector_str).unwrap();
+        document
+            .select(&selector)
+            .map(|loc| loc.text().collect::<String>().trim().to_string())
+            .filter(|loc| loc.starts_with("http://") || loc.starts_with("https://"))
+            .collect::<Vec<_>>()
+    };
+    Sitemap {
+        urls: locs("urlset > url > loc"),
+        sitemaps: locs("sitemapindex > sitemap > loc"),
+    }
+}
+
+// Sitemap text from a download, gunzipping it when it is a .xml.gz file
+pub fn decompress_sitemap(bytes: &[u8]) -> Result<String, ScraperError> {
+    if !bytes.starts_with(&GZIP_MAGIC) {
+        return Ok(String::from_utf8_lossy(bytes).into_owned());
+    }
+    let mut decompressed = Vec::new();
+    GzDecoder::new(bytes).read_to_end(&mut decompressed)?;
+    Ok(String::from_utf8_lossy(&decompressed).into_owned())
+}
@@ -0,0 +1,65 @@
+// store.rs - SQLite sink for analysis results, keyed by URL, for long-running crawls
+
+use std::path::Path;
+use rusqlite::{params, Connection, OptionalExtension};
+use crate::cache::now_secs;
+use crate::error::ScraperError;
+use crate::LLM_run::ContentAnalysis;
+
+pub struct ResultStore {
+    conn: Connection,
+}
+
+impl ResultStore {
+    // Open (creating if needed) the database at `path` and its `analyses` table
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ScraperError> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS analyses (
+                url        TEXT PRIMARY KEY,
+                title      TEXT NOT NULL,
+                summary    TEXT NOT NULL,
+                sentiment  TEXT NOT NULL,
+                topics     TEXT NOT NULL,
+                category   TEXT NOT NULL,
+                fetched_at INTEGER NOT NULL
+            );",
+        )?;
+        Ok(Self { conn })
+    }
+
+    // Whether a result for `url` has already been stored
+    pub fn contains(&self, url: &str) -> Result<bool, ScraperError> {
+        let found = self
+            .conn
+            .query_row("SELECT 1 FROM analyses WHERE url = ?1", params![url], |_| Ok(()))
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    // Insert the analysis of `url`, replacing any earlier result for it. fetched_at is set
+    // to the current time in seconds since the Unix epoch.
+    pub fn upsert_analysis(&self, url: &str, title: &str, analysis: &ContentAnalysis) -> Result<(), ScraperError> {
+        self.conn.execute(
+            "INSERT INTO analyses (url, title, summary, sentiment, topics, category, fetched_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(url) DO UPDATE SET
+                title = excluded.title,
+                summary = excluded.summary,
+                sentiment = excluded.sentiment,
+                topics = excluded.topics,
+                category = excluded.category,
+                fetched_at = excluded.fetched_at",
+            params![
+                url,
+                title,
+                analysis.summary,
+                analysis.sentiment,
+                analysis.key_topics,
+                analysis.category,
+                now_secs() as i64,
+            ],
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+// template.rs - Customizable prompt for analyze_web_content
+
+use std::fs;
+use std::path::Path;
+use crate::error::ScraperError;
+
+// Built-in analysis prompt. {context} expands to a "Page description: ..." line when the
+// page has one, or to nothing.
+pub const DEFAULT_ANALYSIS_TEMPLATE: &str = "Analyze this web content and provide structured analysis:
+
+URL: {url}
+Title: {title}
+{context}Content: {content}
+
+Please provide analysis in this exact format:
+SUMMARY: [2-3 sentence summary]
+SENTIMENT: [POSITIVE/NEGATIVE/NEUTRAL with brief explanation]
+TOPICS: [comma-separated key topics/themes]
+CATEGORY: [main category like Technology, News, Business, Education, etc.]
+
+Be concise and accurate.";
+
+// Analysis prompt with {url}, {title}, {context} and {content} placeholders. Every line of
+// the form `LABEL: ...` with an upper-case label (and no placeholder after the colon)
+// declares a section the response is parsed into.
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    template: String,
+    labels: Vec<String>,
+}
+
+impl Default for PromptTemplate {
+    fn default() -> Self {
+        Self::new(DEFAULT_ANALYSIS_TEMPLATE)
+    }
+}
+
+impl PromptTemplate {
+    pub fn new(template: &str) -> Self {
+        Self {
+            template: template.to_string(),
+            labels: declared_labels(template),
+        }
+    }
+
+    // Load a template from a file; it must contain the {content} placeholder
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ScraperError> {
+        let path = path.as_ref();
+        let template = fs::read_to_string(path)?;
+        if !template.contains("{content}") {
+            return Err(ScraperError::Config(format!(
+                "Prompt template {} has no {{content}} placeholder",
+                path.display()
+            )));
+        }
+        Ok(Self::new(&template))
+    }
+
+    // Section labels the response is expected to use, in template order
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    // Fill in the placeholders. Inserted text is not scanned again, so page content that
+    // happens to contain "{title}" is left alone.
+    pub fn render(&self, url: &str, title: &str, context: Option<&str>, content: &str) -> String {
+        let context_line = match context {
+            Some(context) if !context.trim().is_empty() => format!("Page description: {}\n", context.trim()),
+            _ => String::new(),
+        };
+
+        let mut rendered = String::with_capacity(self.template.len() + content.len());
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find('{') {
+            rendered.push_str(&rest[..start]);
+            let after = &rest[start..];
+            let (value, len) = [
+                ("{url}", url),
+                ("{title}", title),
+                ("{context}", context_line.as_str()),
+                ("{content}", content),
+            ]
+            .into_iter()
+            .find(|(placeholder, _)| after.starts_with(placeholder))
+            .map_or(("{", 1), |(placeholder, value)| (value, placeholder.len()));
+            rendered.push_str(value);
+            rest = &after[len..];
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+}
+
+// Labels of `LABEL: description` lines, skipping header lines such as "URL: {url}"
+fn declared_labels(template: &str) -> Vec<String> {
+    let mut labels: Vec<String> = Vec::new();
+    for line in template.lines() {
+        let Some((label, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        let label = label.trim();
+        let is_label = label.len() >= 2
+            && label.starts_with(|c: char| c.is_ascii_uppercase())
+            && label.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_' || c == ' ');
+        if is_label && !value.contains('{') && !labels.iter().any(|existing| existing == label) {
+            labels.push(label.to_string());
+        }
+    }
+    labels
+}
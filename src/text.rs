@@ -0,0 +1,426 @@
+// text.rs - Text helpers for preparing scraped content for the LLM
+
+use std::collections::hash_map::DefaultHasher;
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use sha2::{Digest, Sha256};
+use whatlang::Lang;
+
+// Longest slug url_slug makes before the hash suffix
+const MAX_SLUG_CHARS: usize = 80;
+
+// File-name-safe name for `url`: its host and path in lower case with runs of other
+// characters turned into '-', plus a short hash of the whole URL so URLs that differ only in
+// punctuation, the query or past the length cap don't collide
+pub fn url_slug(url: &str) -> String {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let mut slug = String::new();
+    for c in without_scheme.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.len() >= MAX_SLUG_CHARS {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    let digest = Sha256::digest(url.as_bytes());
+    let hash: String = digest.iter().take(4).map(|byte| format!("{:02x}", byte)).collect();
+    if slug.is_empty() { hash } else { format!("{}-{}", slug, hash) }
+}
+
+// Rough number of characters per model token
+pub const CHARS_PER_TOKEN: usize = 4;
+
+// Approximate token count of `text`, using the same ratio as truncate_to_tokens
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+// Cut `content` to roughly `max_tokens` tokens, always on a char boundary
+pub fn truncate_to_tokens(content: &str, max_tokens: usize) -> &str {
+    truncate_chars(content, max_tokens.saturating_mul(CHARS_PER_TOKEN))
+}
+
+// Cut `content` to at most `max_chars` characters, always on a char boundary
+pub fn truncate_chars(content: &str, max_chars: usize) -> &str {
+    match content.char_indices().nth(max_chars) {
+        Some((idx, _)) => &content[..idx],
+        None => content,
+    }
+}
+
+// Split `content` into pieces of at most `max_chars` characters, breaking at a paragraph,
+// then a sentence, then a word boundary in the second half of each window when possible
+pub fn chunk_text(content: &str, max_chars: usize) -> Vec<&str> {
+    let max_chars = max_chars.max(1);
+    let mut chunks = Vec::new();
+    let mut rest = content.trim();
+    while !rest.is_empty() {
+        let Some((limit, _)) = rest.char_indices().nth(max_chars) else {
+            chunks.push(rest);
+            break;
+        };
+        let window = &rest[..limit];
+        let in_second_half = |idx: &usize| *idx >= limit / 2;
+        let split = window
+            .rfind("\n\n")
+            .filter(in_second_half)
+            .or_else(|| window.rfind(". ").map(|idx| idx + 1).filter(in_second_half))
+            .or_else(|| window.rfind(char::is_whitespace).filter(|idx| *idx > 0))
+            .unwrap_or(limit);
+
+        let chunk = rest[..split].trim();
+        if !chunk.is_empty() {
+            chunks.push(chunk);
+        }
+        rest = rest[split..].trim_start();
+    }
+    chunks
+}
+
+// Split `content` into sentences at '.', '!' or '?' followed by whitespace, and at line breaks
+pub fn split_sentences(content: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = content.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        let end = match c {
+            '\n' => idx,
+            '.' | '!' | '?' if chars.peek().is_none_or(|(_, next)| next.is_whitespace()) => idx + c.len_utf8(),
+            _ => continue,
+        };
+        let sentence = content[start..end].trim();
+        if !sentence.is_empty() {
+            sentences.push(sentence);
+        }
+        start = end;
+    }
+    let rest = content[start..].trim();
+    if !rest.is_empty() {
+        sentences.push(rest);
+    }
+    sentences
+}
+
+// Sentences this short are headings or fragments, not summary material
+const MIN_SUMMARY_SENTENCE_WORDS: usize = 4;
+
+// The `n` sentences of `content` that best match `keywords`, in their original order, picked
+// without an LLM call. Every keyword occurrence scores a point and earlier sentences get a
+// bonus below one point, which alone decides when no keyword matches.
+pub fn extractive_summary<'a>(content: &'a str, keywords: &[&str], n: usize) -> Vec<&'a str> {
+    let keywords: Vec<String> = keywords
+        .iter()
+        .map(|keyword| keyword.trim().to_lowercase())
+        .filter(|keyword| !keyword.is_empty())
+        .collect();
+    let mut scored: Vec<(usize, f64, &str)> = split_sentences(content)
+        .into_iter()
+        .filter(|sentence| sentence.split_whitespace().count() >= MIN_SUMMARY_SENTENCE_WORDS)
+        .enumerate()
+        .map(|(position, sentence)| {
+            let lower = sentence.to_lowercase();
+            let hits: usize = keywords.iter().map(|keyword| lower.matches(keyword.as_str()).count()).sum();
+            (position, hits as f64 + 1.0 / (position + 2) as f64, sentence)
+        })
+        .collect();
+
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(n);
+    scored.sort_by_key(|(position, _, _)| *position);
+    scored.into_iter().map(|(_, _, sentence)| sentence).collect()
+}
+
+// `text` with each occurrence of a keyword (ignoring ASCII case) wrapped in ** for Markdown bold
+pub fn highlight_keywords(text: &str, keywords: &[&str]) -> String {
+    let keywords: Vec<&str> = keywords
+        .iter()
+        .map(|keyword| keyword.trim())
+        .filter(|keyword| !keyword.is_empty())
+        .collect();
+    let mut highlighted = String::with_capacity(text.len());
+    let mut rest = text;
+    'scan: while let Some(c) = rest.chars().next() {
+        for keyword in &keywords {
+            if let Some(candidate) = rest.get(..keyword.len())
+                && candidate.eq_ignore_ascii_case(keyword)
+            {
+                highlighted.push_str("**");
+                highlighted.push_str(candidate);
+                highlighted.push_str("**");
+                rest = &rest[keyword.len()..];
+                continue 'scan;
+            }
+        }
+        highlighted.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    highlighted
+}
+
+// Longest prefix of `s` that is at most `max` bytes and ends on a char boundary
+pub fn safe_prefix(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let end = s
+        .char_indices()
+        .map(|(idx, _)| idx)
+        .take_while(|&idx| idx <= max)
+        .last()
+        .unwrap_or(0);
+    &s[..end]
+}
+
+// Number of consecutive words in each shingle
+const SHINGLE_WORDS: usize = 5;
+
+// Hashes of every run of SHINGLE_WORDS consecutive words in `content`, ignoring case and
+// punctuation. Text shorter than one shingle yields a single hash of all its words.
+pub fn shingles(content: &str) -> HashSet<u64> {
+    let words: Vec<String> = content
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if words.is_empty() {
+        return HashSet::new();
+    }
+    words
+        .windows(SHINGLE_WORDS.min(words.len()))
+        .map(|window| {
+            let mut hasher = DefaultHasher::new();
+            window.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+// Jaccard similarity (0.0-1.0) of two shingle sets; two empty sets count as identical
+pub fn jaccard_similarity(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+// Topics merged when their normalized forms are at most this many edits apart, by length
+// of the shorter one: exact below 5 characters, one edit up to 9, two from 10
+fn topic_edit_threshold(len: usize) -> usize {
+    match len {
+        0..=4 => 0,
+        5..=9 => 1,
+        _ => 2,
+    }
+}
+
+// Merge near-identical topic strings across pages ("AI", "A.I.", "ai " and "Artificial
+// Intelligence"), ranked by how many pages mention them. Each group is shown under its most
+// common spelling.
+pub fn consolidate_topics<'a>(pages: impl IntoIterator<Item = Vec<&'a str>>) -> Vec<(String, usize)> {
+    struct Group<'a> {
+        key: String,
+        spellings: Vec<(&'a str, usize)>,
+        pages: usize,
+    }
+
+    let mut groups: Vec<Group<'a>> = Vec::new();
+    for topics in pages {
+        let mut counted = HashSet::new();
+        for topic in topics.into_iter().map(str::trim).filter(|topic| !topic.is_empty()) {
+            let key = normalize_topic(topic);
+            if key.is_empty() {
+                continue;
+            }
+            let index = match groups.iter().position(|group| topics_match(&group.key, &key)) {
+                Some(index) => index,
+                None => {
+                    groups.push(Group { key, spellings: Vec::new(), pages: 0 });
+                    groups.len() - 1
+                }
+            };
+            let group = &mut groups[index];
+            match group.spellings.iter_mut().find(|(spelling, _)| *spelling == topic) {
+                Some((_, uses)) => *uses += 1,
+                None => group.spellings.push((topic, 1)),
+            }
+            if counted.insert(index) {
+                group.pages += 1;
+            }
+        }
+    }
+
+    let mut ranked: Vec<(String, usize)> = groups
+        .into_iter()
+        .map(|group| {
+            // max_by_key keeps the last maximum, so reverse to prefer the first spelling seen
+            let spelling = group.spellings.iter().rev().max_by_key(|(_, uses)| *uses).map_or("", |(s, _)| *s);
+            (spelling.to_string(), group.pages)
+        })
+        .collect();
+    // Stable, so equally common topics stay in first-seen order
+    ranked.sort_by_key(|(_, pages)| Reverse(*pages));
+    ranked
+}
+
+// Lower-case letters and digits with single spaces, so "A.I." and "a.i" both become "ai"
+fn normalize_topic(topic: &str) -> String {
+    topic
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_' || c == '/')
+        .map(|word| word.chars().filter(|c| c.is_alphanumeric()).collect::<String>())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Whether two normalized topics name the same thing: within the edit threshold, or one is
+// the initials of the other ("ai" and "artificial intelligence")
+fn topics_match(a: &str, b: &str) -> bool {
+    let shorter = a.chars().count().min(b.chars().count());
+    if edit_distance(a, b) <= topic_edit_threshold(shorter) {
+        return true;
+    }
+    let is_acronym = |short: &str, long: &str| {
+        let initials: String = long.split(' ').filter_map(|word| word.chars().next()).collect();
+        !short.contains(' ') && short.chars().count() >= 2 && long.contains(' ') && initials == short
+    };
+    is_acronym(a, b) || is_acronym(b, a)
+}
+
+// Levenshtein distance between `a` and `b`, counted in chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// ISO 639-1 code of the language `content` is written in, detected locally (no API call).
+// None when the text is too short or mixed for a reliable guess.
+pub fn detect_language(content: &str) -> Option<&'static str> {
+    let info = whatlang::detect(content)?;
+    if !info.is_reliable() {
+        return None;
+    }
+    Some(iso_639_1(info.lang()))
+}
+
+fn iso_639_1(lang: Lang) -> &'static str {
+    match lang {
+        Lang::Afr => "af",
+        Lang::Aka => "ak",
+        Lang::Amh => "am",
+        Lang::Ara => "ar",
+        Lang::Aze => "az",
+        Lang::Bel => "be",
+        Lang::Ben => "bn",
+        Lang::Bul => "bg",
+        Lang::Cat => "ca",
+        Lang::Ces => "cs",
+        Lang::Cmn => "zh",
+        Lang::Cym => "cy",
+        Lang::Dan => "da",
+        Lang::Deu => "de",
+        Lang::Ell => "el",
+        Lang::Eng => "en",
+        Lang::Epo => "eo",
+        Lang::Est => "et",
+        Lang::Fin => "fi",
+        Lang::Fra => "fr",
+        Lang::Guj => "gu",
+        Lang::Heb => "he",
+        Lang::Hin => "hi",
+        Lang::Hrv => "hr",
+        Lang::Hun => "hu",
+        Lang::Hye => "hy",
+        Lang::Ind => "id",
+        Lang::Ita => "it",
+        Lang::Jav => "jv",
+        Lang::Jpn => "ja",
+        Lang::Kan => "kn",
+        Lang::Kat => "ka",
+        Lang::Khm => "km",
+        Lang::Kor => "ko",
+        Lang::Lat => "la",
+        Lang::Lav => "lv",
+        Lang::Lit => "lt",
+        Lang::Mal => "ml",
+        Lang::Mar => "mr",
+        Lang::Mkd => "mk",
+        Lang::Mya => "my",
+        Lang::Nep => "ne",
+        Lang::Nld => "nl",
+        Lang::Nob => "nb",
+        Lang::Ori => "or",
+        Lang::Pan => "pa",
+        Lang::Pes => "fa",
+        Lang::Pol => "pl",
+        Lang::Por => "pt",
+        Lang::Ron => "ro",
+        Lang::Rus => "ru",
+        Lang::Sin => "si",
+        Lang::Slk => "sk",
+        Lang::Slv => "sl",
+        Lang::Sna => "sn",
+        Lang::Spa => "es",
+        Lang::Srp => "sr",
+        Lang::Swe => "sv",
+        Lang::Tam => "ta",
+        Lang::Tel => "te",
+        Lang::Tgl => "tl",
+        Lang::Tha => "th",
+        Lang::Tuk => "tk",
+        Lang::Tur => "tr",
+        Lang::Ukr => "uk",
+        Lang::Urd => "ur",
+        Lang::Uzb => "uz",
+        Lang::Vie => "vi",
+        Lang::Yid => "yi",
+        Lang::Zul => "zu",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncation_counts_characters_not_bytes() {
+        // "é" is two bytes; a byte cut at 4 would land inside the second one
+        assert_eq!(truncate_chars("aéé b", 2), "aé");
+        assert_eq!(truncate_chars("aéé b", 3), "aéé");
+        assert_eq!(truncate_chars("aéé b", 50), "aéé b");
+        assert_eq!(truncate_to_tokens("ab€défgh€", 2), "ab€défgh");
+        assert_eq!(truncate_to_tokens("ab€d", 1), "ab€d");
+    }
+
+    #[test]
+    fn safe_prefix_backs_off_to_a_char_boundary() {
+        // "😀" is bytes 1..5; cutting at 2, 3 or 4 lands inside it
+        let emoji = "a😀b";
+        for max in 1..5 {
+            assert_eq!(safe_prefix(emoji, max), "a");
+        }
+        assert_eq!(safe_prefix(emoji, 5), "a😀");
+
+        // Each CJK char is three bytes
+        let cjk = "日本語";
+        assert_eq!(safe_prefix(cjk, 2), "");
+        assert_eq!(safe_prefix(cjk, 4), "日");
+        assert_eq!(safe_prefix(cjk, 8), "日本");
+        assert_eq!(safe_prefix(cjk, 9), "日本語");
+    }
+}
@@ -0,0 +1,106 @@
+// xpath.rs - XPath 1.0 queries over HTML pages
+//
+// scraper only understands CSS, so the html5ever tree is copied into an sxd-document and
+// queried with sxd-xpath. Both are pure Rust; libxml would need the system libxml2 and
+// bring its own, stricter HTML parser. The price is one extra copy of the tree per page.
+
+use std::collections::HashMap;
+use ego_tree::{NodeId, NodeRef};
+use scraper::{Html, Node};
+use sxd_document::dom::{Document, Element};
+use sxd_document::Package;
+use sxd_xpath::nodeset::Node as XNode;
+use sxd_xpath::{Context, Factory, Value};
+use crate::error::ScraperError;
+
+// Something an XPath expression selected
+#[derive(Debug, Clone)]
+pub(crate) enum XPathMatch {
+    // An element of the scraper document, so its text is extracted like a CSS match
+    Element(NodeId),
+    // A text node, an attribute or the value of an expression like `string(//h1)`
+    Text(String),
+}
+
+// Evaluate every expression in `expressions` against `html`, returning the matches of each
+// in document order. sxd elements hash by node address, so they are fine as map keys despite
+// their interior mutability.
+#[allow(clippy::mutable_key_type)]
+pub(crate) fn select_xpaths(html: &Html, expressions: &[&str]) -> Result<Vec<Vec<XPathMatch>>, ScraperError> {
+    let factory = Factory::new();
+    let compiled = expressions
+        .iter()
+        .map(|expression| {
+            let parse_error = |message: String| ScraperError::SelectorParse {
+                selector: expression.to_string(),
+                message,
+            };
+            factory
+                .build(expression)
+                .map_err(|e| parse_error(e.to_string()))?
+                .ok_or_else(|| parse_error("empty XPath expression".to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let package = Package::new();
+    let document = package.as_document();
+    let mut elements = HashMap::new();
+    copy_children(document, None, html.tree.root(), &mut elements);
+
+    let context = Context::new();
+    compiled
+        .iter()
+        .zip(expressions)
+        .map(|(xpath, expression)| {
+            let value = xpath.evaluate(&context, document.root()).map_err(|e| ScraperError::SelectorParse {
+                selector: expression.to_string(),
+                message: e.to_string(),
+            })?;
+            Ok(match value {
+                Value::Nodeset(nodes) => nodes
+                    .document_order()
+                    .into_iter()
+                    .map(|node| match node {
+                        XNode::Element(element) if elements.contains_key(&element) => {
+                            XPathMatch::Element(elements[&element])
+                        }
+                        other => XPathMatch::Text(other.string_value()),
+                    })
+                    .collect(),
+                other => vec![XPathMatch::Text(other.into_string())],
+            })
+        })
+        .collect()
+}
+
+// Copy the element and text children of `node` under `parent` (the document root when None)
+#[allow(clippy::mutable_key_type)]
+fn copy_children<'d>(
+    document: Document<'d>,
+    parent: Option<Element<'d>>,
+    node: NodeRef<'_, Node>,
+    elements: &mut HashMap<Element<'d>, NodeId>,
+) {
+    for child in node.children() {
+        match child.value() {
+            Node::Element(element) => {
+                let copy = document.create_element(element.name());
+                for (name, value) in element.attrs() {
+                    copy.set_attribute_value(name, value);
+                }
+                match parent {
+                    Some(parent) => parent.append_child(copy),
+                    None => document.root().append_child(copy),
+                }
+                elements.insert(copy, child.id());
+                copy_children(document, Some(copy), child, elements);
+            }
+            Node::Text(text) => {
+                if let Some(parent) = parent {
+                    parent.append_child(document.create_text(text));
+                }
+            }
+            _ => {}
+        }
+    }
+}
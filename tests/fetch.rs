@@ -0,0 +1,141 @@
+// Scraper::fetch against a local mock HTTP server
+
+use std::io::Write;
+use std::time::Duration;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use web_scrapper_project::{Scraper, ScraperError};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn html_page(body: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(body.as_bytes().to_vec(), "text/html; charset=utf-8")
+}
+
+#[tokio::test]
+async fn fetches_an_html_page() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/article"))
+        .respond_with(html_page("<html><head><title>Hello</title></head><body><p>Body text</p></body></html>"))
+        .mount(&server)
+        .await;
+
+    let url = format!("{}/article", server.uri());
+    let page = Scraper::new().unwrap().fetch(&url).await.unwrap();
+    assert_eq!(page.final_url, url);
+    assert!(page.html.contains("<p>Body text</p>"));
+    assert!(page.pdf_text.is_none());
+}
+
+#[tokio::test]
+async fn decodes_gzip_encoded_pages() {
+    let server = MockServer::start().await;
+    let body = "<html><body><p>Compressed text</p></body></html>";
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body.as_bytes()).unwrap();
+    Mock::given(method("GET"))
+        .and(path("/gzip"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Content-Encoding", "gzip")
+                .set_body_raw(encoder.finish().unwrap(), "text/html"),
+        )
+        .mount(&server)
+        .await;
+
+    let page = Scraper::new().unwrap().fetch(&format!("{}/gzip", server.uri())).await.unwrap();
+    assert_eq!(page.html, body);
+
+    let requests = server.received_requests().await.unwrap();
+    let accept_encoding = requests[0].headers.get("accept-encoding").unwrap().to_str().unwrap();
+    assert!(accept_encoding.contains("gzip"), "Accept-Encoding was {}", accept_encoding);
+}
+
+#[tokio::test]
+async fn maps_404_to_http_status() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/missing"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+
+    let url = format!("{}/missing", server.uri());
+    match Scraper::new().unwrap().fetch(&url).await {
+        Err(ScraperError::HttpStatus { url: failed_url, status }) => {
+            assert_eq!(status, 404);
+            assert_eq!(failed_url, url);
+        }
+        other => panic!("expected HttpStatus, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn follows_redirects_to_the_final_url() {
+    let server = MockServer::start().await;
+    let target = format!("{}/new", server.uri());
+    Mock::given(method("GET"))
+        .and(path("/old"))
+        .respond_with(ResponseTemplate::new(301).insert_header("Location", target.as_str()))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/new"))
+        .respond_with(html_page("<html><body><p>Moved here</p></body></html>"))
+        .mount(&server)
+        .await;
+
+    let page = Scraper::new().unwrap().fetch(&format!("{}/old", server.uri())).await.unwrap();
+    assert_eq!(page.final_url, target);
+    assert!(page.html.contains("Moved here"));
+}
+
+#[tokio::test]
+async fn stops_at_max_response_bytes() {
+    let server = MockServer::start().await;
+    let body = format!("<html><body><p>{}</p></body></html>", "x".repeat(4096));
+    Mock::given(method("GET"))
+        .and(path("/huge"))
+        .respond_with(html_page(&body))
+        .mount(&server)
+        .await;
+
+    let url = format!("{}/huge", server.uri());
+    let scraper = Scraper::new().unwrap().with_max_response_bytes(1024);
+    match scraper.fetch(&url).await {
+        Err(ScraperError::ResponseTooLarge { url: failed_url, limit }) => {
+            assert_eq!(limit, 1024);
+            assert_eq!(failed_url, url);
+        }
+        other => panic!("expected ResponseTooLarge, got {:?}", other),
+    }
+
+    let page = Scraper::new().unwrap().with_max_response_bytes(body.len()).fetch(&url).await.unwrap();
+    assert_eq!(page.html, body);
+}
+
+#[tokio::test]
+async fn a_different_cookie_misses_the_page_cache() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/account"))
+        .respond_with(html_page("<html><body><p>Account</p></body></html>"))
+        .mount(&server)
+        .await;
+
+    let cache_dir = tempfile::tempdir().unwrap();
+    let url = format!("{}/account", server.uri());
+    let scraper = |cookie: &str| {
+        Scraper::new().unwrap().with_cookie(cookie).unwrap().with_cache(cache_dir.path(), Duration::from_secs(60)).unwrap()
+    };
+
+    scraper("session=alice").fetch(&url).await.unwrap();
+    scraper("session=alice").fetch(&url).await.unwrap();
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+    scraper("session=bob").fetch(&url).await.unwrap();
+    let anonymous = Scraper::new().unwrap().with_cache(cache_dir.path(), Duration::from_secs(60)).unwrap();
+    anonymous.fetch(&url).await.unwrap();
+    assert_eq!(server.received_requests().await.unwrap().len(), 3);
+}
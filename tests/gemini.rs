@@ -0,0 +1,50 @@
+// GeminiProvider requests against a local mock HTTP server
+
+use std::time::Duration;
+use reqwest::Client;
+use serde_json::json;
+use web_scrapper_project::LLM_run::{GeminiProvider, LlmProvider, DEFAULT_GEMINI_MODEL};
+use web_scrapper_project::{LLMRunner, ScraperError};
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn sends_the_api_key_in_a_header_not_the_url() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(format!("/models/{}:generateContent", DEFAULT_GEMINI_MODEL)))
+        .and(header("x-goog-api-key", "secret-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "candidates": [{"content": {"parts": [{"text": "Hello back"}]}}]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let mut provider = GeminiProvider::new(Client::new(), "secret-key".to_string());
+    provider.set_base_url(&server.uri()).unwrap();
+    assert_eq!(provider.send_prompt("Hello").await.unwrap(), "Hello back");
+
+    let requests = server.received_requests().await.unwrap();
+    let url = &requests[0].url;
+    assert!(url.query_pairs().all(|(name, _)| name != "key"), "key in query string: {}", url);
+    assert!(!url.as_str().contains("secret-key"), "key in URL: {}", url);
+}
+
+#[tokio::test]
+async fn gives_up_when_retry_after_is_over_the_cap() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "3600"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let mut provider = GeminiProvider::new(Client::new(), "secret-key".to_string());
+    provider.set_base_url(&server.uri()).unwrap();
+    let runner = LLMRunner::with_provider(provider).with_retries(3, Duration::from_millis(1));
+    match runner.send_prompt("Hello").await {
+        Err(e @ ScraperError::ApiRequest { .. }) => assert_eq!(e.retry_after(), Some(Duration::from_secs(3600))),
+        other => panic!("expected ApiRequest, got {:?}", other),
+    }
+}